@@ -0,0 +1,23 @@
+//! Checks that `hoard` compiles in a `#![no_std]` crate, with only `alloc`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use hoard::blob::Blob;
+use hoard::ptr::key::{Map, Offset};
+
+/// Encodes a value with its primitive blob encoding.
+pub fn encode<T: Blob>(value: &T) -> Vec<u8> {
+    value.to_blob_bytes()
+}
+
+/// Decodes a `u32` blob from a pile.
+pub fn decode_u32(pile: &[u8], offset: Offset) -> Option<u32> {
+    pile.get_blob_with::<u32, _, _>(offset, (), |bytes| u32::decode_bytes(bytes))
+        .ok()?
+        .ok()
+        .map(|value| value.trust())
+}
@@ -1,10 +1,11 @@
 //! A `Box` equivalent for data behind zone pointers.
 
-use std::marker::PhantomData;
-use std::fmt;
-use std::any;
-use std::mem::ManuallyDrop;
+use core::marker::PhantomData;
+use core::fmt;
+use core::any;
+use core::mem::ManuallyDrop;
 
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 use crate::pointee::Pointee;
@@ -42,7 +43,7 @@ impl<T: ?Sized + Pointee, P: Ptr> Bag<T, P> {
         let this = ManuallyDrop::new(self);
 
         unsafe {
-            (std::ptr::read(&this.ptr),
+            (core::ptr::read(&this.ptr),
              this.metadata)
         }
     }
@@ -129,8 +130,8 @@ where T: fmt::Debug, P: fmt::Debug,
     }
 }
 
-#[derive(Error)]
-#[error("FIXME")]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
 pub enum DecodeBagBytesError<T: ?Sized + BlobDyn, P: PtrBlob> {
     Ptr(P::DecodeBytesError),
     Metadata(<T::Metadata as Blob>::DecodeBytesError),
@@ -1,11 +1,11 @@
 //! Byte sizes for blobs.
 
-use std::convert::TryFrom;
-use std::fmt;
-use std::marker::PhantomData;
-use std::mem::MaybeUninit;
-use std::ops::{Deref, DerefMut};
-use std::slice;
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::slice;
 
 use crate::pointee::Pointee;
 
@@ -120,6 +120,10 @@ where T: BlobDyn + Pointee
         }
     }
 
+    /// # Safety
+    ///
+    /// `ptr` must point to `T::try_size(metadata)` valid bytes, and `metadata` must be valid for
+    /// `T`.
     pub unsafe fn new_unchecked(ptr: *const u8, metadata: T::Metadata) -> Self {
         let ptr = T::make_fat_ptr(ptr as *const (), metadata);
         Self {
@@ -128,6 +132,10 @@ where T: BlobDyn + Pointee
         }
     }
 
+    /// Returns the metadata of the bytes.
+    ///
+    /// Always valid for `T`, as untrusted metadata is checked with `T::try_size` before the bytes
+    /// are created, eg. by `try_from_slice` or a zone's `get_blob_with`.
     pub fn metadata(&self) -> T::Metadata {
         T::metadata(self.ptr)
     }
@@ -173,6 +181,13 @@ pub enum TryFromSliceError<LayoutError> {
     WrongSize,
 }
 
+impl<'a, T: Blob> Bytes<'a, T> {
+    /// Creates new bytes from a slice, returning `None` if the slice isn't exactly `T::SIZE` long.
+    pub fn from_slice(slice: &'a [u8]) -> Option<Self> {
+        Self::try_from(slice).ok()
+    }
+}
+
 impl<'a, T: Blob> TryFrom<&'a [u8]> for Bytes<'a, T> {
     type Error = TryFromSliceError<!>;
 
@@ -211,6 +226,31 @@ impl<'a, T: Blob> TryFrom<&'a mut [MaybeUninit<u8>]> for BytesUninit<'a, T> {
     }
 }
 
+impl<'a, T: Blob> BytesUninit<'a, T> {
+    /// Creates new uninitialized bytes from a buffer, returning `None` if the buffer isn't exactly
+    /// `T::SIZE` long.
+    ///
+    /// Useful for encoding blobs directly, without a saver:
+    ///
+    /// ```
+    /// # use std::mem::MaybeUninit;
+    /// # use hoard::blob::{Blob, Bytes, BytesUninit};
+    /// let mut buf = [MaybeUninit::uninit(); 4];
+    /// let dst = BytesUninit::<u32>::from_slice(&mut buf).unwrap();
+    /// let encoded = 0x12345678u32.encode_bytes(dst);
+    /// assert_eq!(&encoded[..], &[0x78, 0x56, 0x34, 0x12]);
+    ///
+    /// let src = Bytes::<u32>::from_slice(&encoded).unwrap();
+    /// assert_eq!(u32::decode_bytes(src).unwrap().trust(), 0x12345678);
+    ///
+    /// let mut short = [MaybeUninit::uninit(); 3];
+    /// assert!(BytesUninit::<u32>::from_slice(&mut short).is_none());
+    /// ```
+    pub fn from_slice(slice: &'a mut [MaybeUninit<u8>]) -> Option<Self> {
+        Self::try_from(slice).ok()
+    }
+}
+
 impl<'a, T: ?Sized + BlobDyn> BytesUninit<'a, T> {
     pub fn from_bytes(slice: &'a mut [u8], metadata: T::Metadata) -> Result<Self, T::LayoutError> {
         let blob_size = T::try_size(metadata)?;
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use super::Blob;
 
 pub trait Encoder {
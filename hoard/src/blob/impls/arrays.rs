@@ -1,18 +1,20 @@
 use super::*;
 
-use std::any::type_name;
-use std::fmt;
+use core::any::type_name;
+use core::fmt;
 
-#[derive(Error, Debug)]
-#[error("FIXME")]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
 pub struct DecodeArrayBytesError<E: fmt::Debug, const N: usize> {
     idx: usize,
     err: E,
 }
 
 /*
-#[derive(Error, Debug)]
-#[error("FIXME")]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
 pub struct ValidateArrayError<E: fmt::Debug, const N: usize> {
     idx: usize,
     err: E,
@@ -1,6 +1,7 @@
 use super::*;
 
-use std::task::Poll;
+use core::task::Poll;
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 pub mod arrays;
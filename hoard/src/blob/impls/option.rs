@@ -1,8 +1,9 @@
 use super::*;
 
-#[derive(Error, Debug)]
-#[error("FIXME")]
-pub enum DecodeOptionBytesError<E: std::fmt::Debug> {
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
+pub enum DecodeOptionBytesError<E: core::fmt::Debug> {
     Discriminant,
     Padding,
     Value(E),
@@ -1,12 +1,16 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 use super::*;
 
 use crate::pointee::SliceLayoutError;
 
-#[derive(Debug, Error)]
-#[error("FIXME")]
-pub struct DecodeSliceBytesError<E: std::error::Error> {
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
+pub struct DecodeSliceBytesError<E: crate::error::Error> {
     idx: usize,
     err: E,
 }
@@ -70,7 +74,7 @@ mod test {
     #[test]
     fn encode_decode_roundtrip() {
         #[track_caller]
-        fn t<T: Blob + Eq + std::fmt::Debug>(src: &[T], expected_bytes: &[u8]) {
+        fn t<T: Blob + Eq + core::fmt::Debug>(src: &[T], expected_bytes: &[u8]) {
             let actual_bytes = src.to_blob_bytes_dyn();
             assert_eq!(actual_bytes, expected_bytes);
 
@@ -1,11 +1,13 @@
-use std::error::Error;
+use crate::error::Error;
 
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 use super::*;
 
-#[derive(Debug, Error, PartialEq, Eq)]
-#[error("FIXME")]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
 pub enum DecodeTupleBytesError<
     T0: Error,
     T1: Error = !,
@@ -102,7 +104,7 @@ tuple! ( T11, T10, T9, T8, T7, T6, T5, T4, T3, T2, T1, T0, );
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fmt;
+    use core::fmt;
 
     #[test]
     fn encode_bytes() {
@@ -149,4 +151,21 @@ mod tests {
         e::<(bool,)>(&[3], DecodeTupleBytesError::T0(DecodeBoolError));
         e::<(bool,bool)>(&[1,3], DecodeTupleBytesError::T1(DecodeBoolError));
     }
+
+    #[test]
+    fn zero_sized_fields() {
+        use core::marker::PhantomData;
+
+        type T = (PhantomData<u8>, u32, ());
+        assert_eq!(<T as Blob>::SIZE, 4);
+        assert_eq!(<PhantomData<[u8]> as Blob>::SIZE, 0);
+        assert_eq!(<() as Blob>::SIZE, 0);
+
+        let value: T = (PhantomData, 0x12345678, ());
+        let buf = value.to_blob_bytes();
+        assert_eq!(buf, &[0x78, 0x56, 0x34, 0x12]);
+
+        let bytes = Bytes::<T>::try_from(&buf[..]).unwrap();
+        assert_eq!(<T as Blob>::decode_bytes(bytes).unwrap().trust(), value);
+    }
 }
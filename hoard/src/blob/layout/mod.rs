@@ -1,5 +1,5 @@
-use std::ops::Range;
-use std::borrow::Cow;
+use core::ops::Range;
+use alloc::borrow::Cow;
 
 mod cowref;
 use self::cowref::CowRef;
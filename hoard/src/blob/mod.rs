@@ -4,8 +4,10 @@
 //! compilers layout of values in memory: each (sized) type serializes to a fixed size byte array,
 //! with structs simply concatenating together every field.
 
-use std::convert::TryFrom;
-use std::marker::PhantomData;
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+
+use alloc::{vec, vec::Vec};
 
 pub mod bytes;
 pub use self::bytes::{Bytes, BytesUninit, ValidBytes};
@@ -20,7 +22,7 @@ pub use crate::validate::MaybeValid;
 /// A sized type with a fixed-size binary serialization.
 pub trait Blob : 'static + Sized {
     const SIZE: usize;
-    type DecodeBytesError : 'static + std::error::Error + Send;
+    type DecodeBytesError : 'static + crate::error::Error + Send;
 
     fn encode_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self>;
     fn decode_bytes(blob: Bytes<'_, Self>) -> Result<MaybeValid<Self>, Self::DecodeBytesError>;
@@ -49,7 +51,7 @@ pub trait Blob : 'static + Sized {
 ///
 /// This trait has a blanket implementation for all `T: Blob`.
 pub unsafe trait BlobDyn : 'static + Pointee + IntoOwned {
-    type DecodeBytesError : 'static + std::error::Error + Send;
+    type DecodeBytesError : 'static + crate::error::Error + Send;
 
     fn try_size(metadata: Self::Metadata) -> Result<usize, Self::LayoutError>;
 
@@ -1,5 +1,5 @@
-use std::task::Poll;
-use std::marker::PhantomData;
+use core::task::Poll;
+use core::marker::PhantomData;
 
 pub mod bytes;
 pub use self::bytes::{Bytes, BytesUninit, ValidBytes};
@@ -15,8 +15,8 @@ pub use crate::maybevalid::MaybeValid;
 pub trait Blob : 'static + Sized {
     const SIZE: usize;
     type Ptr : PtrBlob;
-    type DecodeBytesError : 'static + std::error::Error;
-    type ValidateError : 'static + std::error::Error;
+    type DecodeBytesError : 'static + crate::error::Error;
+    type ValidateError : 'static + crate::error::Error;
     type ValidatePoll : ValidatePoll<Ptr = Self::Ptr, Error = Self::ValidateError>;
 
     fn encode_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self>;
@@ -27,7 +27,7 @@ pub trait Blob : 'static + Sized {
 
 pub trait Validator {
     type Ptr : PtrBlob;
-    type Error : 'static + std::error::Error;
+    type Error : 'static + crate::error::Error;
 
     fn check_blob<T: ?Sized + BlobDyn, F, R>(
         &mut self,
@@ -80,7 +80,7 @@ where V: Validator,
 
 pub trait ValidatePoll {
     type Ptr : PtrBlob;
-    type Error : 'static + std::error::Error;
+    type Error : 'static + crate::error::Error;
 
     fn validate_poll_impl<V>(&mut self, validator: &mut V) -> Poll<Result<(), Self::Error>>
         where V: Validator<Ptr = Self::Ptr>;
@@ -106,8 +106,8 @@ impl ValidatePoll for () {
 /// Dynamically sized blob.
 pub unsafe trait BlobDyn : 'static + Pointee + IntoOwned {
     type Ptr : PtrBlob;
-    type DecodeBytesError : 'static + std::error::Error;
-    type ValidateError : 'static + std::error::Error;
+    type DecodeBytesError : 'static + crate::error::Error;
+    type ValidateError : 'static + crate::error::Error;
     type ValidatePoll : ValidatePoll<Ptr = Self::Ptr, Error = Self::ValidateError>;
 
     fn try_size(metadata: Self::Metadata) -> Result<usize, Self::LayoutError>;
@@ -145,7 +145,7 @@ unsafe impl<T: Blob> BlobDyn for T {
 /*
 pub trait ValidateImpl {
     type Ptr : PtrBlob;
-    type Error : 'static + std::error::Error;
+    type Error : 'static + crate::error::Error;
 }
 
 pub trait ValidateBlob<'a> : ValidateImpl {
@@ -162,7 +162,7 @@ pub trait ValidateBlobDyn<'a> : ValidateImpl {
 
 pub trait ValidatePoll {
     type Ptr : PtrBlob;
-    type Error : 'static + std::error::Error;
+    type Error : 'static + crate::error::Error;
 
     fn validate_poll_impl<V>(&mut self, validator: &mut V) -> Poll<Result<(), Self::Error>>
         where V: Validator<Ptr = Self::Ptr>;
@@ -186,6 +186,6 @@ impl ValidatePoll for () {
 
 pub trait Validator {
     type Ptr : PtrBlob;
-    type Error : 'static + std::error::Error;
+    type Error : 'static + crate::error::Error;
 }
 */
@@ -0,0 +1,14 @@
+//! The error trait hoard's error types are bounded by.
+//!
+//! With the `std` feature this is simply `std::error::Error`. `core` has no error trait, so
+//! without `std` a stand-in is used that only requires `Debug`.
+
+#[cfg(feature = "std")]
+pub use std::error::Error;
+
+/// Stand-in for `std::error::Error` when built without `std`.
+#[cfg(not(feature = "std"))]
+pub trait Error : core::fmt::Debug {}
+
+#[cfg(not(feature = "std"))]
+impl<E: ?Sized + core::fmt::Debug> Error for E {}
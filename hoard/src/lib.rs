@@ -1,3 +1,5 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
 #![feature(never_type)]
 
 #![feature(unwrap_infallible)]
@@ -13,6 +15,10 @@
 #![allow(unused_variables)]
 #![allow(dead_code)]
 
+extern crate alloc;
+
+pub mod error;
+
 pub mod owned;
 
 pub mod validate;
@@ -38,6 +44,7 @@ pub mod prelude {
         pointee::Pointee,
         ptr::{
             AsZone,
+            Alloc,
             Ptr,
             TryGet, TryGetMut,
             Get, GetMut,
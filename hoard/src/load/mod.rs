@@ -1,6 +1,6 @@
 //! Loading data behind zone pointers.
 
-use std::task::Poll;
+use core::task::Poll;
 
 use crate::blob::{Blob, BlobDyn, Bytes};
 use crate::pointee::Pointee;
@@ -4,6 +4,8 @@ use core::borrow::Borrow;
 use core::ptr;
 use core::mem::ManuallyDrop;
 
+use alloc::vec::Vec;
+
 pub mod refown;
 pub use self::refown::RefOwn;
 
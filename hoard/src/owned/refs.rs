@@ -1,5 +1,5 @@
-use std::ops::Deref;
-use std::borrow::Borrow;
+use core::ops::Deref;
+use core::borrow::Borrow;
 
 use super::IntoOwned;
 
@@ -9,6 +9,29 @@ pub enum Ref<'a, T: ?Sized + IntoOwned> {
     Owned(T::Owned),
 }
 
+impl<T: ?Sized + IntoOwned> AsRef<T> for Ref<'_, T> {
+    /// Returns a reference to the value, whether borrowed or owned.
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized + IntoOwned> Borrow<T> for Ref<'_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, T: Clone> Ref<'a, T> {
+    /// Converts into an owned value, cloning only if the value is borrowed.
+    pub fn into_owned(self) -> T {
+        match self {
+            Ref::Borrowed(r) => r.clone(),
+            Ref::Owned(owned) => owned,
+        }
+    }
+}
+
 impl<T: ?Sized + IntoOwned> Deref for Ref<'_, T> {
     type Target = T;
 
@@ -43,3 +66,37 @@ where A: PartialEq<B>
         self.deref() != *other
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::rc::Rc;
+
+    #[test]
+    fn into_owned() {
+        let value = Rc::new(42u8);
+
+        let borrowed: Ref<Rc<u8>> = Ref::Borrowed(&value);
+        assert_eq!(**borrowed.as_ref(), 42);
+        let owned = borrowed.into_owned();
+        assert_eq!(Rc::strong_count(&value), 2);
+
+        // Owned values are moved, not cloned
+        let moved: Ref<Rc<u8>> = Ref::Owned(owned);
+        assert_eq!(**moved.as_ref(), 42);
+        let owned = moved.into_owned();
+        assert_eq!(Rc::strong_count(&value), 2);
+        assert!(Rc::ptr_eq(&owned, &value));
+    }
+
+    #[test]
+    fn as_ref_unsized() {
+        let slice: &[u8] = &[1, 2, 3];
+        assert_eq!(Ref::Borrowed(slice).as_ref(), &[1, 2, 3]);
+        assert_eq!(Ref::<[u8]>::Owned(vec![4, 5]).as_ref(), &[4, 5]);
+
+        let owned: Ref<[u8]> = Ref::Owned(vec![6]);
+        assert_eq!(Borrow::<[u8]>::borrow(&owned), &[6]);
+    }
+}
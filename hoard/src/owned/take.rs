@@ -1,6 +1,8 @@
 use core::mem::ManuallyDrop;
 use core::ptr;
 
+use alloc::vec::Vec;
+
 use super::RefOwn;
 
 pub unsafe trait Take<T: ?Sized> : Sized {
@@ -1,18 +1,34 @@
 //! Functionality for working with pointer metadata.
 
-use std::fmt;
-use std::ptr::{self, NonNull};
+use core::alloc::Layout;
+use core::fmt;
+use core::ptr::{self, NonNull};
 
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 use crate::blob::Blob;
 
 pub trait Pointee {
     type Metadata : 'static + Copy + Blob + fmt::Debug + Eq + Ord;
-    type LayoutError : 'static + std::error::Error + Send;
+    type LayoutError : 'static + crate::error::Error + Send;
 
     fn metadata(this: *const Self) -> Self::Metadata;
 
+    /// Gets the metadata of a pointer, failing if it isn't valid for `Self`.
+    ///
+    /// `metadata` may assume the pointer was made by `make_fat_ptr`; use this instead when the
+    /// metadata was derived from an untrusted source.
+    fn try_metadata(this: *const Self) -> Result<Self::Metadata, MetadataError> {
+        Ok(Self::metadata(this))
+    }
+
+    /// Returns the layout of a value with the given metadata.
+    ///
+    /// Fails if no valid value could have that metadata, eg. a slice longer than `isize::MAX`
+    /// bytes.
+    fn try_layout(metadata: Self::Metadata) -> Result<Layout, Self::LayoutError>;
+
     fn sized_metadata() -> Self::Metadata
         where Self: Sized
     {
@@ -43,6 +59,10 @@ impl<T> Pointee for T {
         ()
     }
 
+    fn try_layout(_: ()) -> Result<Layout, !> {
+        Ok(Layout::new::<T>())
+    }
+
     fn sized_metadata() -> Self::Metadata {
         ()
     }
@@ -56,8 +76,15 @@ impl<T> Pointee for T {
     }
 }
 
-#[derive(Debug, Error, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[error("FIXME")]
+/// Returned by `Pointee::try_metadata` when a pointer's metadata is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("invalid pointer metadata"))]
+pub struct MetadataError;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
 #[non_exhaustive]
 pub struct SliceLayoutError;
 
@@ -70,6 +97,16 @@ impl<T> Pointee for [T] {
         this.len()
     }
 
+    /// Fails if the slice would be larger than `isize::MAX` bytes.
+    fn try_metadata(this: *const Self) -> Result<usize, MetadataError> {
+        let len = this.len();
+        Self::try_layout(len).map(|_| len).map_err(|_| MetadataError)
+    }
+
+    fn try_layout(len: usize) -> Result<Layout, SliceLayoutError> {
+        Layout::array::<T>(len).map_err(|_| SliceLayoutError)
+    }
+
     fn make_fat_ptr(thin: *const (), len: usize) -> *const Self {
         ptr::slice_from_raw_parts(thin as *const T, len)
     }
@@ -78,3 +115,59 @@ impl<T> Pointee for [T] {
         ptr::slice_from_raw_parts_mut(thin as *mut T, len)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sized_try_layout() {
+        let layout: Result<Layout, !> = <u64 as Pointee>::try_layout(());
+        assert_eq!(layout.into_ok(), Layout::new::<u64>());
+
+        let layout: Result<Layout, !> = <(u8, u32) as Pointee>::try_layout(());
+        assert_eq!(layout.into_ok(), Layout::new::<(u8, u32)>());
+    }
+
+    #[test]
+    fn try_metadata() {
+        let x = 42u64;
+        assert_eq!(<u64 as Pointee>::try_metadata(&x), Ok(()));
+
+        let xs = [1u8, 2, 3];
+        assert_eq!(<[u8] as Pointee>::try_metadata(&xs[..]), Ok(3));
+    }
+
+    #[test]
+    fn slice_try_layout() {
+        assert_eq!(<[u16] as Pointee>::try_layout(0), Ok(Layout::new::<[u16; 0]>()));
+        assert_eq!(<[u16] as Pointee>::try_layout(3), Ok(Layout::new::<[u16; 3]>()));
+        assert_eq!(<[u16] as Pointee>::try_layout(usize::MAX), Err(SliceLayoutError));
+        assert_eq!(<[()] as Pointee>::try_layout(usize::MAX), Ok(Layout::new::<()>()));
+    }
+
+    #[test]
+    fn slice_size_overflow() {
+        use crate::blob::BlobDyn;
+
+        type Big = [u8; 4096];
+
+        // Fits in isize::MAX bytes
+        let max_len = isize::MAX as usize / 4096;
+        assert!(<[Big] as Pointee>::try_layout(max_len).is_ok());
+        assert_eq!(<[Big] as BlobDyn>::try_size(max_len), Ok(max_len * 4096));
+
+        // Doesn't, but doesn't overflow usize either
+        assert_eq!(<[Big] as Pointee>::try_layout(max_len + 1), Err(SliceLayoutError));
+        assert_eq!(<[Big] as BlobDyn>::try_size(max_len + 1), Err(SliceLayoutError));
+
+        // Overflows usize
+        assert_eq!(<[Big] as Pointee>::try_layout(usize::MAX / 2), Err(SliceLayoutError));
+        assert_eq!(<[Big] as BlobDyn>::try_size(usize::MAX / 2), Err(SliceLayoutError));
+
+        let huge: *const [Big] = ptr::slice_from_raw_parts(NonNull::dangling().as_ptr(), max_len + 1);
+        assert_eq!(<[Big] as Pointee>::try_metadata(huge), Err(MetadataError));
+        let ok: *const [Big] = ptr::slice_from_raw_parts(NonNull::dangling().as_ptr(), max_len);
+        assert_eq!(<[Big] as Pointee>::try_metadata(ok), Ok(max_len));
+    }
+}
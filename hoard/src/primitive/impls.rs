@@ -1,10 +1,11 @@
 use super::*;
 
+#[cfg(feature = "std")]
 use thiserror::Error;
 
-use std::convert::TryFrom;
-use std::mem;
-use std::num;
+use core::convert::TryFrom;
+use core::mem;
+use core::num;
 
 impl Primitive for ! {
     const BLOB_SIZE: usize = 0;
@@ -36,9 +37,25 @@ impl Primitive for () {
     }
 }
 
-#[derive(Error, Debug, PartialEq, Eq)]
+impl<T: ?Sized + 'static> Primitive for PhantomData<T> {
+    const BLOB_SIZE: usize = 0;
+    type DecodeBytesError = !;
+
+    #[inline(always)]
+    fn encode_blob_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
+        dst.write_bytes(&[])
+    }
+
+    #[inline(always)]
+    fn decode_blob_bytes(_blob: Bytes<'_, Self>) -> Result<Self, Self::DecodeBytesError> {
+        Ok(PhantomData)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Error))]
 #[non_exhaustive]
-#[error("FIXME")]
+#[cfg_attr(feature = "std", error("FIXME"))]
 pub struct DecodeBoolError;
 
 impl Primitive for bool {
@@ -60,6 +77,29 @@ impl Primitive for bool {
     }
 }
 
+/// Returned when a `char` blob isn't a valid Unicode scalar value.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[non_exhaustive]
+#[cfg_attr(feature = "std", error("invalid char {0:#x}"))]
+pub struct DecodeCharError(pub u32);
+
+impl Primitive for char {
+    const BLOB_SIZE: usize = 4;
+    type DecodeBytesError = DecodeCharError;
+
+    #[inline(always)]
+    fn encode_blob_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
+        dst.write_bytes(&u32::from(*self).to_le_bytes())
+    }
+
+    #[inline(always)]
+    fn decode_blob_bytes(blob: Bytes<'_, Self>) -> Result<Self, Self::DecodeBytesError> {
+        let n = u32::from_le_bytes(TryFrom::try_from(&*blob).unwrap());
+        char::from_u32(n).ok_or(DecodeCharError(n))
+    }
+}
+
 macro_rules! impl_ints {
     ($($t:ty,)+) => {$(
         impl Primitive for $t {
@@ -88,9 +128,10 @@ impl_ints! {
     i8, i16, i32, i64, i128,
 }
 
-#[derive(Error, Debug)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 #[non_exhaustive]
-#[error("FIXME")]
+#[cfg_attr(feature = "std", error("FIXME"))]
 pub struct DecodeNonZeroIntError;
 
 macro_rules! impl_nonzero_ints {
@@ -118,3 +159,63 @@ impl_nonzero_ints! {
     u8 => num::NonZeroU8, u16 => num::NonZeroU16, u32 => num::NonZeroU32, u64 => num::NonZeroU64, u128 => num::NonZeroU128,
     i8 => num::NonZeroI8, i16 => num::NonZeroI16, i32 => num::NonZeroI32, i64 => num::NonZeroI64, i128 => num::NonZeroI128,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::fmt::Debug;
+
+    fn primitive_roundtrip<T: Primitive + PartialEq + Debug>(values: &[T]) {
+        for value in values {
+            let buf = value.to_blob_bytes();
+            assert_eq!(buf.len(), T::BLOB_SIZE);
+
+            let bytes = Bytes::<T>::try_from(&buf[..]).unwrap();
+            let decoded = T::decode_blob_bytes(bytes).ok().unwrap();
+            assert_eq!(&decoded, value);
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        macro_rules! t {
+            ($($t:ty,)+) => {$(
+                primitive_roundtrip::<$t>(&[0, 1, <$t>::MIN, <$t>::MAX]);
+            )+}
+        }
+        t! {
+            usize,
+            u8, u16, u32, u64, u128,
+            i8, i16, i32, i64, i128,
+        }
+
+        primitive_roundtrip(&[false, true]);
+        primitive_roundtrip(&['\0', 'a', '~', 'é', '\u{d7ff}', '\u{e000}', '\u{1f980}', char::MAX]);
+        primitive_roundtrip(&[(), ()]);
+    }
+
+    #[test]
+    fn char_encoding() {
+        assert_eq!('a'.to_blob_bytes(), &[0x61, 0, 0, 0]);
+        assert_eq!('\u{1f980}'.to_blob_bytes(), &[0x80, 0xf9, 0x01, 0]);
+    }
+
+    #[test]
+    fn char_rejects_invalid() {
+        for &n in [0xd800, 0xdbff, 0xdc00, 0xdfff, 0x110000, u32::MAX].iter() {
+            let buf = n.to_le_bytes();
+            let bytes = Bytes::<char>::try_from(&buf[..]).unwrap();
+            assert_eq!(char::decode_blob_bytes(bytes), Err(DecodeCharError(n)));
+        }
+    }
+
+    #[test]
+    fn bool_rejects_non_canonical() {
+        for b in 2 ..= 255u8 {
+            let buf = [b];
+            let bytes = Bytes::<bool>::try_from(&buf[..]).unwrap();
+            assert_eq!(bool::decode_blob_bytes(bytes), Err(DecodeBoolError));
+        }
+    }
+}
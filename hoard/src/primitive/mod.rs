@@ -1,6 +1,6 @@
 //! Serialization of types that never have internal pointers.
 
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 use crate::blob::*;
 use crate::load::Load;
@@ -9,7 +9,7 @@ use crate::save::{Save, SavePoll, Saver};
 pub mod impls;
 
 pub trait Primitive : 'static + Copy {
-    type DecodeBytesError : 'static + std::error::Error + Send;
+    type DecodeBytesError : 'static + crate::error::Error + Send;
     const BLOB_SIZE: usize;
 
     fn encode_blob_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self>;
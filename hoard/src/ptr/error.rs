@@ -1,11 +1,15 @@
-use std::error;
+use crate::error;
 
+use alloc::boxed::Box;
+
+#[cfg(feature = "std")]
 use thiserror::Error;
 
 use super::*;
 
-#[derive(Debug, Error)]
-#[error("FIXME")]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("FIXME"))]
 pub struct Error<ZoneId: fmt::Debug, ZoneError: error::Error> {
     inner: Box<Inner<ZoneId, ZoneError>>,
 }
@@ -1,6 +1,6 @@
-use std::ptr::NonNull;
-use std::alloc::Layout;
-use std::mem;
+use core::ptr::NonNull;
+use core::alloc::Layout;
+use core::mem;
 
 use super::*;
 
@@ -20,16 +20,16 @@ impl Heap {
     #[inline]
     unsafe fn heap_dealloc(ptr: NonNull<()>, layout: Layout) {
         if layout.size() > 0 {
-            std::alloc::dealloc(ptr.cast().as_ptr(), layout)
+            alloc::alloc::dealloc(ptr.cast().as_ptr(), layout)
         }
     }
 
     #[inline]
     unsafe fn heap_alloc(layout: Layout) -> NonNull<()> {
         if layout.size() > 0 {
-            let ptr = std::alloc::alloc(layout);
+            let ptr = alloc::alloc::alloc(layout);
             NonNull::new(ptr.cast())
-                    .unwrap_or_else(|| std::alloc::handle_alloc_error(layout))
+                    .unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
         } else {
             NonNull::new_unchecked(layout.align() as *mut ())
         }
@@ -56,7 +56,7 @@ impl Ptr for Heap {
     unsafe fn dealloc<T: ?Sized + Pointee>(&mut self, metadata: T::Metadata) {
         let r = self.try_get_dirty_mut::<T>(metadata).into_ok().trust();
         let layout = Layout::for_value(r);
-        std::ptr::drop_in_place::<T>(r);
+        core::ptr::drop_in_place::<T>(r);
         Self::heap_dealloc(NonNull::from(r).cast(), layout)
     }
 
@@ -104,7 +104,7 @@ impl Ptr for Heap {
 
             unsafe {
                 let dst = Self::heap_alloc(layout);
-                std::ptr::copy_nonoverlapping::<u8>(
+                core::ptr::copy_nonoverlapping::<u8>(
                     &*src as *const T as *const u8,
                     dst.as_ptr().cast(),
                     layout.size()
@@ -117,6 +117,22 @@ impl Ptr for Heap {
     }
 }
 
+/// The heap zone.
+impl Alloc for () {
+    type Ptr = Heap;
+    type Error = !;
+
+    #[inline]
+    fn try_alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Result<Bag<T, Heap>, !> {
+        Ok(Heap::alloc(src))
+    }
+
+    #[inline]
+    fn alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Bag<T, Heap> {
+        Heap::alloc(src)
+    }
+}
+
 impl TryGet for Heap {
     type Error = !;
 
@@ -189,6 +205,56 @@ mod tests {
         let bag = Heap::alloc(42u8);
     }
 
+    #[test]
+    fn alloc_slice() {
+        let bag: Bag<[u32], Heap> = ().alloc_slice([1u32, 2, 3].iter().copied());
+        assert_eq!(bag.metadata(), 3);
+        assert_eq!(bag.try_get_dirty().into_ok(), &[1, 2, 3]);
+
+        let bag: Bag<[u32], Heap> = ().alloc_slice(core::iter::empty());
+        assert_eq!(bag.metadata(), 0);
+        assert_eq!(bag.try_get_dirty().into_ok(), &[]);
+
+        let v = bag.try_take_dirty().into_ok();
+        assert!(v.is_empty());
+    }
+
+    /// Heap arena that fails once `remaining` allocations have been made.
+    struct Bounded {
+        remaining: usize,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Exhausted;
+
+    impl Alloc for Bounded {
+        type Ptr = Heap;
+        type Error = Exhausted;
+
+        fn try_alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Result<Bag<T, Heap>, Exhausted> {
+            self.remaining = self.remaining.checked_sub(1).ok_or(Exhausted)?;
+            Ok(Heap::alloc(src))
+        }
+    }
+
+    #[test]
+    fn try_alloc_bounded() {
+        let mut arena = Bounded { remaining: 2 };
+        let bag = arena.try_alloc(1u8).unwrap();
+        assert_eq!(bag.try_get_dirty().into_ok(), &1u8);
+        let bag = arena.alloc_slice([2u8, 3].iter().copied());
+        assert_eq!(bag.try_get_dirty().into_ok(), &[2, 3]);
+
+        assert_eq!(arena.try_alloc(4u8).err(), Some(Exhausted));
+    }
+
+    #[test]
+    #[should_panic(expected = "allocation failed")]
+    fn alloc_bounded_panics() {
+        let mut arena = Bounded { remaining: 0 };
+        arena.alloc(1u8);
+    }
+
     #[test]
     fn zero_sized_does_not_alloc() {
         let bag = Heap::alloc(());
@@ -0,0 +1,226 @@
+//! Maps that cache recently loaded blobs.
+
+use core::cell::RefCell;
+
+use alloc::{collections::BTreeMap, rc::Rc};
+
+use crate::blob::{BlobDyn, Bytes};
+
+use super::{Map, Offset};
+
+/// Wraps a `Map`, caching the bytes of the most recently loaded blobs.
+///
+/// Repeated loads of the same blobs, eg. the internal nodes of a tree serving many inclusion
+/// proofs, are served from the cache without touching the inner map. Only blob bytes are cached,
+/// so values are still decoded on every load: this pays off when the inner map is expensive to
+/// read from, not for in-memory `[u8]` piles, where a hit saves no more than a copy.
+///
+/// Cached blobs are reference counted, so evicting a blob never invalidates bytes that are still
+/// being decoded. Lookups and evictions take O(log n) in the number of cached blobs.
+#[derive(Debug)]
+pub struct CachingZone<M: Map<Key = Offset>> {
+    cache: RefCell<Cache>,
+    capacity: usize,
+    inner: M,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    // The blob at each offset, and when it was last used
+    blobs: BTreeMap<Offset, (Rc<[u8]>, u64)>,
+
+    // Offsets by when they were last used, least recently used first
+    lru: BTreeMap<u64, Offset>,
+    tick: u64,
+}
+
+impl<M: Map<Key = Offset>> CachingZone<M> {
+    /// Creates a new cache of up to `capacity` blobs.
+    pub fn new(inner: M, capacity: usize) -> Self {
+        Self {
+            cache: RefCell::default(),
+            capacity,
+            inner,
+        }
+    }
+
+    /// Returns the maximum number of blobs cached.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of blobs currently cached.
+    pub fn len(&self) -> usize {
+        self.cache.borrow().blobs.len()
+    }
+
+    /// Returns true if no blobs are cached.
+    pub fn is_empty(&self) -> bool {
+        self.cache.borrow().blobs.is_empty()
+    }
+
+    /// Evicts every cached blob.
+    pub fn clear(&self) {
+        let mut cache = self.cache.borrow_mut();
+        cache.blobs.clear();
+        cache.lru.clear();
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> M {
+        self.inner
+    }
+
+    fn get_cached(&self, key: Offset, size: usize) -> Option<Rc<[u8]>> {
+        let cache = &mut *self.cache.borrow_mut();
+        let (blob, last_used) = cache.blobs.get_mut(&key)
+                                           .filter(|(blob, _)| blob.len() == size)?;
+
+        cache.lru.remove(last_used);
+        cache.tick += 1;
+        *last_used = cache.tick;
+        cache.lru.insert(cache.tick, key);
+        Some(Rc::clone(blob))
+    }
+
+    fn insert(&self, key: Offset, blob: Rc<[u8]>) {
+        let cache = &mut *self.cache.borrow_mut();
+        if let Some((_, last_used)) = cache.blobs.remove(&key) {
+            cache.lru.remove(&last_used);
+        } else if cache.blobs.len() >= self.capacity {
+            if let Some((_, evicted)) = cache.lru.pop_first() {
+                cache.blobs.remove(&evicted);
+            }
+        }
+
+        cache.tick += 1;
+        cache.blobs.insert(key, (blob, cache.tick));
+        cache.lru.insert(cache.tick, key);
+    }
+}
+
+impl<M: Map<Key = Offset>> Map for CachingZone<M> {
+    type Id = M::Id;
+    type Error = M::Error;
+    type Key = Offset;
+
+    #[inline]
+    fn id(&self) -> Self::Id {
+        self.inner.id()
+    }
+
+    fn get_blob_with<T: ?Sized, F, R>(&self, key: Offset, metadata: T::Metadata, f: F) -> Result<R, Self::Error>
+        where F: FnOnce(Bytes<T>) -> R,
+              T: BlobDyn
+    {
+        // Invalid metadata is left to the inner map to report
+        let size = match T::try_size(metadata) {
+            Ok(size) if self.capacity > 0 => size,
+            _ => return self.inner.get_blob_with(key, metadata, f),
+        };
+
+        let blob = match self.get_cached(key, size) {
+            Some(blob) => blob,
+            None => {
+                let blob: Rc<[u8]> = self.inner.get_blob_with(key, metadata, |bytes: Bytes<T>| {
+                    Rc::from(&bytes[..])
+                })?;
+                self.insert(key, Rc::clone(&blob));
+                blob
+            }
+        };
+
+        // SAFETY: the blob was loaded with the same metadata, and we hold a reference to it
+        let bytes = unsafe { Bytes::new_unchecked(blob.as_ptr(), metadata) };
+        Ok(f(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::ptr::key::TracingZone;
+
+    #[test]
+    fn cache_hits() {
+        let buf: &[u8] = &[0x12, 0x34, 0x56, 0x78];
+        let zone = CachingZone::new(TracingZone::new(buf), 2);
+
+        let get_u8 = |n| zone.get_blob_with::<u8, _, _>(Offset::new(n), (), |bytes| bytes[0]);
+        assert_eq!(get_u8(0), Ok(0x12));
+        assert_eq!(get_u8(1), Ok(0x34));
+        assert_eq!(zone.inner().take_trace(), vec![Offset::new(0), Offset::new(1)]);
+        assert_eq!(zone.len(), 2);
+
+        // Cached
+        assert_eq!(get_u8(0), Ok(0x12));
+        assert_eq!(get_u8(1), Ok(0x34));
+        assert!(zone.inner().take_trace().is_empty());
+
+        // Evicts the least recently used blob, offset 0
+        assert_eq!(get_u8(2), Ok(0x56));
+        assert_eq!(get_u8(1), Ok(0x34));
+        assert_eq!(get_u8(0), Ok(0x12));
+        assert_eq!(zone.inner().take_trace(), vec![Offset::new(2), Offset::new(0)]);
+        assert_eq!(zone.len(), 2);
+
+        // Same offset, different size
+        let r = zone.get_blob_with::<u16, _, _>(Offset::new(0), (), |bytes| bytes.to_vec());
+        assert_eq!(r, Ok(vec![0x12, 0x34]));
+        assert_eq!(zone.inner().take_trace(), vec![Offset::new(0)]);
+
+        // Errors aren't cached
+        assert!(get_u8(5).is_err());
+        assert!(get_u8(5).is_err());
+        assert_eq!(zone.inner().take_trace(), vec![Offset::new(5), Offset::new(5)]);
+
+        zone.clear();
+        assert_eq!(zone.len(), 0);
+        assert!(zone.is_empty());
+    }
+
+    #[test]
+    fn invalid_metadata() {
+        let buf: &[u8] = &[0x12, 0x34, 0x56, 0x78];
+        let zone = CachingZone::new(TracingZone::new(buf), 2);
+
+        // Reported by the inner map, rather than panicking
+        let err = zone.get_blob_with::<[u16], _, _>(Offset::new(0), usize::MAX, |_| ()).unwrap_err();
+        assert_eq!(err.to_string(), "invalid blob metadata");
+        assert_eq!(zone.len(), 0);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let buf: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7];
+        let zone = CachingZone::new(TracingZone::new(buf), 4);
+
+        let get_u8 = |n| zone.get_blob_with::<u8, _, _>(Offset::new(n), (), |bytes| bytes[0]);
+        for n in 0 .. 4 {
+            assert_eq!(get_u8(n), Ok(n as u8));
+        }
+
+        // Using 0 and 2 leaves 1 as the least recently used, then 3
+        assert_eq!(get_u8(0), Ok(0));
+        assert_eq!(get_u8(2), Ok(2));
+        assert_eq!(get_u8(4), Ok(4));
+        assert_eq!(get_u8(5), Ok(5));
+        assert_eq!(zone.len(), 4);
+        zone.inner().take_trace();
+
+        for &n in &[0, 2, 4, 5] {
+            assert_eq!(get_u8(n), Ok(n as u8));
+        }
+        assert!(zone.inner().take_trace().is_empty());
+
+        assert_eq!(get_u8(1), Ok(1));
+        assert_eq!(get_u8(3), Ok(3));
+        assert_eq!(zone.inner().take_trace(), vec![Offset::new(1), Offset::new(3)]);
+    }
+}
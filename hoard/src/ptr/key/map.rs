@@ -1,10 +1,11 @@
-use std::convert::TryFrom;
-use std::error;
-use std::fmt;
-use std::ptr::NonNull;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ptr::NonNull;
 
+#[cfg(feature = "std")]
 use thiserror::Error;
 
+use crate::error;
 use crate::blob::{BlobDyn, Bytes};
 
 use super::*;
@@ -19,6 +20,11 @@ pub trait Map {
     fn get_blob_with<T: ?Sized, F, R>(&self, key: Self::Key, metadata: T::Metadata, f: F) -> Result<R, Self::Error>
         where F: FnOnce(Bytes<T>) -> R,
               T: BlobDyn;
+
+    /// Starts a read transaction, borrowing the map until every value read through it is dropped.
+    fn read_txn(&self) -> ReadTxn<'_, Self> {
+        ReadTxn::new(self)
+    }
 }
 
 impl<M: ?Sized + Map> Map for &'_ M {
@@ -43,9 +49,20 @@ impl<'a, M: ?Sized + Map> Zone for &'a M {
     type Id = M::Id;
 }
 
-#[derive(Debug, Error, PartialEq, Eq)]
-#[error("FIXME")]
-pub struct SliceError;
+/// Returned when a blob can't be loaded from a slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[non_exhaustive]
+pub enum SliceError {
+    #[cfg_attr(feature = "std", error("blob at offset {offset:?} of size {size} out of bounds"))]
+    OutOfBounds {
+        offset: Offset,
+        size: usize,
+    },
+
+    #[cfg_attr(feature = "std", error("invalid blob metadata"))]
+    Metadata,
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SliceId(NonNull<[u8]>);
@@ -65,11 +82,12 @@ impl Map for [u8] {
         where F: FnOnce(Bytes<T>) -> R,
               T: BlobDyn
     {
-        let len = T::try_size(metadata).expect("valid metadata");
+        let size = T::try_size(metadata).map_err(|_| SliceError::Metadata)?;
+        let out_of_bounds = SliceError::OutOfBounds { offset, size };
 
-        let start: usize = usize::try_from(offset.get()).ok().ok_or(SliceError)?;
-        let end = start.checked_add(len).ok_or(SliceError)?;
-        let buf: &[u8] = self.get(start .. end).ok_or(SliceError)?;
+        let start: usize = usize::try_from(offset.get()).ok().ok_or(out_of_bounds)?;
+        let end = start.checked_add(size).ok_or(out_of_bounds)?;
+        let buf: &[u8] = self.get(start .. end).ok_or(out_of_bounds)?;
 
         let bytes = unsafe { Bytes::new_unchecked(buf.as_ptr(), metadata) };
 
@@ -94,6 +112,10 @@ mod tests {
         }).unwrap();
 
         assert_eq!(buf.get_blob_with::<u16, _, _>(Offset::new(2), (), |_| ()).unwrap_err(),
-                   SliceError);
+                   SliceError::OutOfBounds { offset: Offset::new(2), size: 2 });
+        assert_eq!(buf.get_blob_with::<u8, _, _>(Offset::new(u64::MAX), (), |_| ()).unwrap_err(),
+                   SliceError::OutOfBounds { offset: Offset::new(u64::MAX), size: 1 });
+        assert_eq!(buf.get_blob_with::<[u8], _, _>(Offset::new(0), usize::MAX, |_| ()).unwrap_err(),
+                   SliceError::Metadata);
     }
 }
@@ -1,5 +1,6 @@
-use std::error;
-use std::mem::ManuallyDrop;
+use core::mem::ManuallyDrop;
+
+use crate::error;
 
 use super::*;
 
@@ -9,6 +10,15 @@ pub use self::offset::Offset;
 pub mod map;
 pub use self::map::Map;
 
+pub mod tracing;
+pub use self::tracing::TracingZone;
+
+pub mod caching;
+pub use self::caching::CachingZone;
+
+pub mod txn;
+pub use self::txn::ReadTxn;
+
 #[derive(Debug)]
 pub struct Key<'a, M: ?Sized, K = <M as Map>::Key> {
     key: K,
@@ -118,6 +128,9 @@ impl<'a, M: ?Sized, K> From<Key<'a, M, K>> for KeyMut<'a, M, K> {
     }
 }
 
+/// Mutable pointers into an in-memory pile.
+pub type PileMut<'a, M> = KeyMut<'a, M>;
+
 impl<M: ?Sized, K> Default for KeyMut<'_, M, K> {
     fn default() -> Self {
         KeyMut::Heap(Heap::default())
@@ -157,7 +170,10 @@ impl<'a, M: ?Sized + Map> Ptr for KeyMut<'a, M> {
     unsafe fn try_take_dirty_then<T: ?Sized + Pointee, F, R>(self, metadata: T::Metadata, f: F) -> Result<R, Self::Clean>
         where F: FnOnce(MaybeValid<RefOwn<T>>) -> R
     {
-        todo!()
+        match self {
+            KeyMut::Key(key) => Err(key),
+            KeyMut::Heap(ptr) => Ok(ptr.try_take_dirty_then(metadata, f).into_ok()),
+        }
     }
 
     fn alloc<T: ?Sized + Pointee>(src: impl Take<T>) -> Bag<T, Self> {
@@ -169,6 +185,20 @@ impl<'a, M: ?Sized + Map> Ptr for KeyMut<'a, M> {
     }
 }
 
+/// New values are allocated on the heap until saved to the pile.
+impl<'a, M: ?Sized + Map> Alloc for &'a M {
+    type Ptr = KeyMut<'a, M>;
+    type Error = !;
+
+    fn try_alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Result<Bag<T, Self::Ptr>, !> {
+        Ok(KeyMut::alloc(src))
+    }
+
+    fn alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Bag<T, Self::Ptr> {
+        KeyMut::alloc(src)
+    }
+}
+
 impl<'a, M: ?Sized + Map> TryGet for KeyMut<'a, M> {
     type Error = Error<M::Id, M::Error>;
 
@@ -254,6 +284,8 @@ impl<'a, M: ?Sized + Map> GetMut for KeyMut<'a, M> {
 mod test {
     use super::*;
 
+    use super::map::SliceError;
+
     #[test]
     fn key() {
         let map: &[u8] = &[];
@@ -273,9 +305,50 @@ mod test {
         assert_eq!(r, &0x78563412);
     }
 
+    #[test]
+    fn truncated_pile() {
+        let map: &[u8] = &[0x12, 0x34, 0x56];
+
+        let key = Key::from_blob(Offset::new(1), &map);
+        let err = unsafe { key.try_get::<u32>(()).unwrap_err() };
+        assert!(matches!(err.kind(),
+                         ErrorKind::Zone(SliceError::OutOfBounds { offset, size: 4 }) if *offset == 1));
+
+        let err = unsafe { key.try_take::<u32>(()).unwrap_err() };
+        assert!(matches!(err.kind(), ErrorKind::Zone(SliceError::OutOfBounds { .. })));
+
+        let mut key_mut = KeyMut::from(Key::from_blob(Offset::new(3), &map));
+        let err = unsafe { key_mut.try_get_mut::<u8>(()).unwrap_err() };
+        assert!(matches!(err.kind(), ErrorKind::Zone(SliceError::OutOfBounds { .. })));
+
+        // Failing to load leaves the key clean
+        assert!(matches!(key_mut, KeyMut::Key(_)));
+    }
+
     #[test]
     fn keymut() {
         let bag: Bag<u8, KeyMut<[u8]>> = KeyMut::alloc(42u8);
         dbg!(bag.get());
     }
+
+    #[test]
+    fn alloc_in_pile() {
+        let mut pile: &[u8] = &[];
+        let bag: Bag<u8, PileMut<[u8]>> = pile.alloc(42u8);
+        assert_eq!(*bag.get(), 42);
+        assert!(matches!(bag.ptr(), KeyMut::Heap(_)));
+    }
+
+    #[test]
+    fn keymut_try_take_dirty() {
+        let map: &[u8] = &[42];
+        let bag: Bag<u8, PileMut<[u8]>> = KeyMut::alloc(12u8);
+        let (ptr, metadata) = bag.into_raw_parts();
+        let r = unsafe { ptr.try_take_dirty::<u8>(metadata) };
+        assert_eq!(r.unwrap().trust(), 12);
+
+        let key = Key::from_blob(Offset::new(0), &map);
+        let r = unsafe { KeyMut::from(key).try_take_dirty::<u8>(()) };
+        assert!(matches!(r, Err(k) if k.to_blob() == Offset::new(0)));
+    }
 }
@@ -1,23 +1,37 @@
-use std::marker::PhantomData;
-use std::convert::TryFrom;
-use std::cmp;
+use core::marker::PhantomData;
+use core::convert::TryFrom;
+use core::cmp;
+use core::ops::Range;
 
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec, vec::Vec};
+
+#[cfg(feature = "std")]
 use thiserror::Error;
 
+use crate::error;
 use crate::blob::{BlobDyn, Bytes, BytesUninit};
 use crate::primitive::Primitive;
 use crate::ptr::{Ptr, PtrClean, PtrBlob, AsZone, TryGet, Zone};
-use crate::save::{SaveRef, SaveRefPoll, Saver};
-use crate::load::LoadRef;
+use crate::save::{Save, SaveRef, SaveRefPoll, Saver};
+use crate::load::{Load, LoadRef};
 use crate::pointee::Pointee;
 
 use super::{Key, Map};
 
 /// Slice offset.
+///
+/// Offsets are ordered by byte position within the pile, the value returned by `get`. Sorting
+/// offsets therefore sorts blobs by where they start.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Offset(u64);
 
 impl Offset {
+    /// The largest possible offset.
+    pub const MAX: Self = Self(u64::MAX);
+
     #[inline]
     pub const fn new(n: u64) -> Self {
         Self(n)
@@ -27,6 +41,48 @@ impl Offset {
     pub const fn get(self) -> u64 {
         self.0
     }
+
+    /// Returns the byte range of a blob of length `len` at this offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the end of the region does not fit in a `usize`.
+    #[inline]
+    pub fn region(self, len: usize) -> Range<usize> {
+        let start = usize::try_from(self.0).expect("offset out of range");
+        let end = start.checked_add(len).expect("offset out of range");
+        start .. end
+    }
+
+    /// Compares two offsets by byte position.
+    ///
+    /// This is the ordering used by `Ord`. Offsets are compared by their `get` values, so this
+    /// stays correct even if the encoding of `Offset` changes.
+    #[inline]
+    pub fn cmp_position(self, other: Self) -> cmp::Ordering {
+        self.get().cmp(&other.get())
+    }
+
+    /// Moves the offset by `delta` bytes, eg. to relocate a blob copied to a different position.
+    ///
+    /// Returns `None` if the result would be negative or greater than `MAX`.
+    #[inline]
+    pub fn rebase(self, delta: i64) -> Option<Self> {
+        if delta >= 0 {
+            self.0.checked_add(delta as u64)
+        } else {
+            self.0.checked_sub(delta.unsigned_abs())
+        }.map(Self)
+    }
+}
+
+/// Returns true if two byte regions overlap.
+///
+/// Empty regions never overlap anything, and adjacent regions do not overlap.
+#[inline]
+pub fn regions_overlap(a: Range<usize>, b: Range<usize>) -> bool {
+    !a.is_empty() && !b.is_empty()
+        && a.start < b.end && b.start < a.end
 }
 
 impl Primitive for Offset {
@@ -45,6 +101,20 @@ impl Primitive for Offset {
     }
 }
 
+impl cmp::PartialOrd for Offset {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl cmp::Ord for Offset {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.cmp_position(*other)
+    }
+}
+
 impl From<!> for Offset {
     #[inline]
     fn from(never: !) -> Self { never }
@@ -79,11 +149,17 @@ impl_cmp! {
 }
 
 
+/// A pile with nothing in it, the starting point for saving new values.
+pub const EMPTY_PILE: &[u8] = &[];
+
+/// Saves only the dirty parts of a value, appending them to an existing map.
+///
+/// Clean keys are already present in `map`, so they're reused as-is rather than copied. The saved
+/// bytes must be appended to `map` for the returned offsets to be valid. Same as
+/// `OffsetSaver::with_base`.
 #[derive(Debug)]
 pub struct DirtyOffsetSaver<'m, M: ?Sized> {
-    map: &'m M,
-    initial_offset: usize,
-    dst: Vec<u8>,
+    inner: OffsetSaver<'m, M>,
 }
 
 impl<'m, M: ?Sized> DirtyOffsetSaver<'m, M>
@@ -91,74 +167,397 @@ where M: AsRef<[u8]>,
 {
     pub fn new(map: &'m M) -> Self {
         Self {
-            initial_offset: map.as_ref().len(),
-            map,
-            dst: vec![],
+            inner: OffsetSaver {
+                base: Some(map.as_ref().len()),
+                map,
+                dst: vec![],
+                progress: Progress::default(),
+            },
         }
     }
 }
 
-/*
 impl<'m, M: ?Sized> DirtyOffsetSaver<'m, M>
-where M: Map<Key = Offset>
+where M: Map<Key = Offset> + AsRef<[u8]>
 {
-    pub fn save<T: ?Sized>(mut self, value: &T) -> (Offset, Vec<u8>)
-        where T: SaveRef<Key<'m, M>, Offset>
+    /// Saves a value, returning its offset and the bytes to append to the map.
+    pub fn try_save<T: ?Sized>(self, value: &T) -> Result<(Offset, Vec<u8>), Box<dyn error::Error>>
+        where T: SaveRef<Offset>,
+              Key<'m, M>: From<T::PtrClean>,
+              &'m M: AsZone<<T::PtrClean as PtrClean>::Zone>,
     {
-        let mut poll = value.init_save_ref();
-
-        poll.save_ref_poll(&mut self).into_ok();
-
-        let metadata = poll.blob_metadata();
-        let offset = self.save_blob_with(poll.blob_metadata(), |dst| {
-            poll.encode_blob_dyn_bytes(dst)
-        }).into_ok();
+        self.inner.try_save(value)
+    }
 
-        (offset, self.dst)
+    /// Saves a value, returning the offset of its blob without consuming the saver.
+    pub fn save<T: ?Sized>(&mut self, value: &T) -> Result<Offset, Box<dyn error::Error>>
+        where T: SaveRef<Offset>,
+              Key<'m, M>: From<T::PtrClean>,
+              &'m M: AsZone<<T::PtrClean as PtrClean>::Zone>,
+    {
+        self.inner.save(value)
     }
 }
-*/
 
 #[derive(Debug)]
 pub struct OffsetSaver<'m, M: ?Sized> {
     map: &'m M,
+    base: Option<usize>,
     dst: Vec<u8>,
+    progress: Progress,
+}
+
+/// Returned by `OffsetSaver` when saving fails, recording where in the save the failure happened.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("failed to save blob {blob} at depth {depth}: {source}"))]
+pub struct SaveError {
+    /// Number of blobs successfully saved by the failing `save` call before the failure.
+    pub blob: usize,
+
+    /// Depth of the failing blob, counting from the value being saved.
+    pub depth: usize,
+
+    /// Offset of the clean blob being copied when the failure happened, if any.
+    pub offset: Option<Offset>,
+
+    #[cfg_attr(feature = "std", source)]
+    pub source: Box<dyn error::Error + Send>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Progress {
+    blobs: usize,
+    depth: usize,
+}
+
+impl OffsetSaver<'static, [u8]> {
+    /// Creates a saver for a new pile, starting from `EMPTY_PILE`.
+    pub fn empty() -> Self {
+        Self::new(EMPTY_PILE)
+    }
 }
 
 impl<'m, M: ?Sized> OffsetSaver<'m, M>
 where M: Map<Key = Offset> + AsRef<[u8]>
 {
+    /// Creates a saver for a new pile, copying every blob reachable from the saved value.
     pub fn new(map: &'m M) -> Self {
         Self {
             map,
+            base: None,
             dst: vec![],
+            progress: Progress::default(),
         }
     }
 
-    pub fn try_save<T: ?Sized>(mut self, value: &T) -> Result<(Offset, Vec<u8>), Box<dyn std::error::Error>>
+    /// Creates a saver that appends to an existing `base` pile.
+    ///
+    /// Clean keys into `base` are already present, so they're reused as-is rather than copied, and
+    /// only dirty data is saved. Keys into any other map are copied. The saved bytes must be
+    /// appended to `base` for the returned offsets to be valid.
+    pub fn with_base(base: &'m M) -> Self {
+        Self {
+            base: Some(base.as_ref().len()),
+            map: base,
+            dst: vec![],
+            progress: Progress::default(),
+        }
+    }
+
+    pub fn try_save<T: ?Sized>(mut self, value: &T) -> Result<(Offset, Vec<u8>), Box<dyn error::Error>>
         where T: SaveRef<Offset>,
               Key<'m, M>: From<T::PtrClean>,
               &'m M: AsZone<<T::PtrClean as PtrClean>::Zone>,
     {
+        let offset = self.save(value)?;
+        Ok((offset, self.finish()))
+    }
 
-        let wrapper: &mut Wrapper<Self, T::PtrClean> = Wrapper::new(&mut self);
+    /// Saves a value, returning the offset of its blob without consuming the saver.
+    ///
+    /// Any number of values can be saved before calling `finish`. Blobs are written in order, with
+    /// every blob a value points to written before the value itself, so offsets returned by
+    /// earlier saves remain valid in the finished pile.
+    ///
+    /// Errors are returned as a `SaveError`, recording which blob failed.
+    pub fn save<T: ?Sized>(&mut self, value: &T) -> Result<Offset, Box<dyn error::Error>>
+        where T: SaveRef<Offset>,
+              Key<'m, M>: From<T::PtrClean>,
+              &'m M: AsZone<<T::PtrClean as PtrClean>::Zone>,
+    {
+        self.progress = Progress::default();
+        let wrapper: &mut Wrapper<Self, T::PtrClean> = Wrapper::new(self);
 
         let mut poll = value.init_save_ref();
-        let offset = wrapper.poll_ref::<T::SaveRefPoll>(&mut poll)?;
+        wrapper.poll_ref::<T::SaveRefPoll>(&mut poll)
+    }
+
+    /// Finishes saving, returning the saved bytes.
+    ///
+    /// For a `with_base` saver these are the bytes to append to the base.
+    pub fn finish(self) -> Vec<u8> {
+        self.dst
+    }
+
+    /// Finishes saving, appending an index of named `roots` to the saved bytes.
+    ///
+    /// The index is a little-endian `u64` count of entries, followed by each entry's `u64`
+    /// name length, UTF-8 name, and `u64` offset. The length of the index in bytes is written last,
+    /// as a `u64`, so `read_index` can find it from the end of the pile. Offsets of blobs are
+    /// unaffected, as the index is written after them.
+    pub fn finish_with_index(self, roots: &[(&str, Offset)]) -> Vec<u8> {
+        let mut dst = self.dst;
+        let start = dst.len();
+
+        dst.extend_from_slice(&(roots.len() as u64).to_le_bytes());
+        for (name, offset) in roots {
+            dst.extend_from_slice(&(name.len() as u64).to_le_bytes());
+            dst.extend_from_slice(name.as_bytes());
+            dst.extend_from_slice(&offset.get().to_le_bytes());
+        }
+
+        let index_len = (dst.len() - start) as u64;
+        dst.extend_from_slice(&index_len.to_le_bytes());
+        dst
+    }
+
+    /// Writes the saved bytes to `writer`, preceded by a `FileHeader` pointing to `root`.
+    ///
+    /// The bytes saved by a `with_base` saver aren't a complete pile, so write them after the base
+    /// instead.
+    #[cfg(feature = "std")]
+    pub fn into_file(self, root: Offset, mut writer: impl Write) -> io::Result<()> {
+        let header = FileHeader::new(root);
+        writer.write_all(&header.to_bytes())?;
+        writer.write_all(&self.dst)
+    }
+}
+
+/// Fixed-size header at the start of a pile file.
+///
+/// Layout is the 4-byte magic, a little-endian `u32` version, and the little-endian `u64` root offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileHeader {
+    pub version: u32,
+    pub root: Offset,
+}
 
-        Ok((offset, self.dst))
+impl FileHeader {
+    pub const MAGIC: [u8; 4] = *b"hord";
+    pub const VERSION: u32 = 0;
+    pub const SIZE: usize = 16;
+
+    #[inline]
+    pub const fn new(root: Offset) -> Self {
+        Self { version: Self::VERSION, root }
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut buf = [0; Self::SIZE];
+        buf[0 .. 4].copy_from_slice(&Self::MAGIC);
+        buf[4 .. 8].copy_from_slice(&self.version.to_le_bytes());
+        buf[8 .. 16].copy_from_slice(&self.root.get().to_le_bytes());
+        buf
+    }
+
+    pub fn from_bytes(buf: &[u8; Self::SIZE]) -> Result<Self, LoadFileError> {
+        if buf[0 .. 4] != Self::MAGIC {
+            return Err(LoadFileError::Magic);
+        }
+
+        let version = u32::from_le_bytes(TryFrom::try_from(&buf[4 .. 8]).unwrap());
+        if version != Self::VERSION {
+            return Err(LoadFileError::Version(version));
+        }
+
+        let root = u64::from_le_bytes(TryFrom::try_from(&buf[8 .. 16]).unwrap());
+        Ok(Self { version, root: Offset::new(root) })
     }
 }
 
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+pub enum LoadFileError {
+    #[cfg_attr(feature = "std", error("empty file, missing pile header"))]
+    Empty,
+
+    #[cfg_attr(feature = "std", error("bad magic bytes"))]
+    Magic,
+
+    #[cfg_attr(feature = "std", error("unsupported version {0}"))]
+    Version(u32),
+
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "std", error(transparent))]
+    Io(#[from] io::Error),
+}
+
+/// Returned by `read_index` when a pile's index is malformed.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Error))]
+pub enum ReadIndexError {
+    #[cfg_attr(feature = "std", error("index truncated"))]
+    Truncated,
+
+    #[cfg_attr(feature = "std", error("index name is not valid UTF-8"))]
+    Name,
+
+    #[cfg_attr(feature = "std", error("{0} trailing bytes after index entries"))]
+    Trailing(usize),
+}
+
+/// Reads the index of named roots written by `OffsetSaver::finish_with_index`.
+pub fn read_index(pile: &[u8]) -> Result<Vec<(String, Offset)>, ReadIndexError> {
+    fn take<'a>(buf: &mut &'a [u8], n: usize) -> Result<&'a [u8], ReadIndexError> {
+        if buf.len() < n {
+            return Err(ReadIndexError::Truncated);
+        }
+        let (head, tail) = buf.split_at(n);
+        *buf = tail;
+        Ok(head)
+    }
+
+    fn take_u64(buf: &mut &[u8]) -> Result<u64, ReadIndexError> {
+        let bytes = take(buf, 8)?;
+        Ok(u64::from_le_bytes(TryFrom::try_from(bytes).unwrap()))
+    }
+
+    let body_len = pile.len().checked_sub(8).ok_or(ReadIndexError::Truncated)?;
+    let (body, mut index_len) = pile.split_at(body_len);
+    let index_len = usize::try_from(take_u64(&mut index_len)?).map_err(|_| ReadIndexError::Truncated)?;
+    let index_start = body.len().checked_sub(index_len).ok_or(ReadIndexError::Truncated)?;
+
+    let mut buf = &body[index_start ..];
+    let count = take_u64(&mut buf)?;
+
+    let mut roots = vec![];
+    for _ in 0 .. count {
+        let name_len = usize::try_from(take_u64(&mut buf)?).map_err(|_| ReadIndexError::Truncated)?;
+        let name = core::str::from_utf8(take(&mut buf, name_len)?).map_err(|_| ReadIndexError::Name)?;
+        let offset = Offset::new(take_u64(&mut buf)?);
+        roots.push((String::from(name), offset));
+    }
+
+    if !buf.is_empty() {
+        return Err(ReadIndexError::Trailing(buf.len()));
+    }
+    Ok(roots)
+}
+
+/// Reads a pile file written by `OffsetSaver::into_file`, returning the root offset and the pile bytes.
+#[cfg(feature = "std")]
+pub fn load_from_file(mut reader: impl Read) -> Result<(Offset, Vec<u8>), LoadFileError> {
+    let mut buf = [0; FileHeader::SIZE];
+    let n = loop {
+        match reader.read(&mut buf) {
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            r => break r?,
+        }
+    };
+    if n == 0 {
+        return Err(LoadFileError::Empty);
+    }
+    reader.read_exact(&mut buf[n ..])?;
+    let header = FileHeader::from_bytes(&buf)?;
+
+    let mut pile = vec![];
+    reader.read_to_end(&mut pile)?;
+    Ok((header.root, pile))
+}
+
+/// Saves a newly allocated value, appending its blobs to `pile` and returning its offset.
+///
+/// This is the write-through counterpart to allocating into a `PileMut`: the value can't contain
+/// clean keys, as those would borrow the pile being appended to, so only values whose pointers
+/// are all dirty (eg. `Heap`) can be saved.
+pub fn save_new<T: ?Sized>(pile: &mut Vec<u8>, value: &T) -> Result<Offset, Box<dyn error::Error>>
+    where T: SaveRef<Offset>,
+          for<'m> Key<'m, [u8]>: From<T::PtrClean>,
+          for<'m> &'m [u8]: AsZone<<T::PtrClean as PtrClean>::Zone>,
+{
+    let base: &[u8] = pile;
+    let (offset, blobs) = OffsetSaver::with_base(base).try_save(value)?;
+    pile.extend_from_slice(&blobs);
+    Ok(offset)
+}
+
+/// Returned by `join_and_save`.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+pub enum JoinAndSaveError<E> {
+    #[cfg_attr(feature = "std", error("failed to load left value: {0}"))]
+    Load(Box<dyn error::Error + Send>),
+
+    #[cfg_attr(feature = "std", error(transparent))]
+    Join(E),
+
+    #[cfg_attr(feature = "std", error("failed to save joined value: {0}"))]
+    Save(Box<dyn error::Error>),
+}
+
+/// Joins the value saved at `left_root` with `right`, saving only the new blobs.
+///
+/// The left value is loaded once, with its pointers as clean `PileMut` keys into `pile`, and
+/// handed to `join` along with `right`. Those keys are reused as-is when the joined value is
+/// saved, so growing a value this way never copies what has already been saved.
+///
+/// Returns the offset of the joined value and the bytes to append to `pile`, same as
+/// `DirtyOffsetSaver::try_save`.
+pub fn join_and_save<'m, T, R, E>(
+    pile: &'m [u8],
+    left_root: Offset,
+    right: R,
+    join: impl FnOnce(T, R) -> Result<T, E>,
+) -> Result<(Offset, Vec<u8>), JoinAndSaveError<E>>
+    where T: Save<Offset> + Load<Zone = &'m [u8]>,
+          Key<'m, [u8]>: From<T::PtrClean>,
+          &'m [u8]: AsZone<<T::PtrClean as PtrClean>::Zone>,
+{
+    let left = pile.get_blob_with(left_root, (), |bytes: Bytes<'_, T::Blob>| {
+        <T as LoadRef>::load_owned_from_bytes(bytes, &pile)
+    }).map_err(|err| JoinAndSaveError::Load(Box::new(err)))?
+      .map_err(|err| JoinAndSaveError::Load(Box::new(err)))?
+      .trust();
+
+    let joined = join(left, right).map_err(JoinAndSaveError::Join)?;
+    DirtyOffsetSaver::new(pile).try_save(&joined).map_err(JoinAndSaveError::Save)
+}
+
+
+/// Copies every blob reachable from the `T` at `root` into a new pile, dropping the garbage left
+/// behind by incremental saves.
+///
+/// Returns the compacted pile along with the new offset of the root.
+pub fn compact<'m, T>(pile: &'m [u8], root: Offset) -> Result<(Vec<u8>, Offset), Box<dyn error::Error>>
+    where T: SaveRef<Offset>,
+          Key<'m, [u8]>: From<T::PtrClean>,
+          &'m [u8]: AsZone<T::Zone> + AsZone<<T::PtrClean as PtrClean>::Zone>,
+{
+    let mut saver = OffsetSaver::new(pile);
+    let key = Key::from_blob(root, &pile);
+
+    let wrapper: &mut Wrapper<OffsetSaver<'m, [u8]>, Key<'m, [u8]>> = Wrapper::new(&mut saver);
+    let offset = match wrapper.save_ptr::<T>(key, T::sized_metadata())? {
+        Ok(offset) => offset,
+        Err(mut poll) => wrapper.poll_ref(&mut poll)?,
+    };
+    Ok((saver.finish(), offset))
+}
 
 trait BlobSaver {
-    type MapError : std::error::Error + 'static + Send;
-    type SaveError : std::error::Error + 'static + Send;
+    type MapError : error::Error + 'static + Send;
+    type SaveError : error::Error + 'static + Send;
 
     type Key : PtrClean;
 
     fn zone(&self) -> &<Self::Key as PtrClean>::Zone;
 
+    /// Best-effort offset of a key, for error reporting.
+    fn key_offset(key: Self::Key) -> Option<Offset>;
+
+    fn progress_mut(&mut self) -> &mut Progress;
+
     fn get_blob_with<T: ?Sized, F, R>(
         &self,
         key: Self::Key,
@@ -178,7 +577,7 @@ trait BlobSaver {
 }
 
 impl<'m, M: ?Sized> BlobSaver for OffsetSaver<'m, M>
-where M: Map
+where M: Map<Key = Offset>
 {
     type MapError = M::Error;
     type SaveError = !;
@@ -189,6 +588,14 @@ where M: Map
         &self.map
     }
 
+    fn key_offset(key: Self::Key) -> Option<Offset> {
+        Some(key.key)
+    }
+
+    fn progress_mut(&mut self) -> &mut Progress {
+        &mut self.progress
+    }
+
     fn get_blob_with<T: ?Sized, F, R>(
         &self,
         key: Self::Key,
@@ -198,8 +605,12 @@ where M: Map
         where T: BlobDyn,
               F: FnOnce(Bytes<'_, T>) -> R
     {
-        let r = self.map.get_blob_with(key.key, metadata, f)?;
-        Ok(Err(r))
+        if self.base.is_some() && core::ptr::eq(key.map, self.map) {
+            Ok(Ok(key.key))
+        } else {
+            let r = key.map.get_blob_with(key.key, metadata, f)?;
+            Ok(Err(r))
+        }
     }
 
     fn save_blob_with<T: ?Sized, F>(
@@ -211,15 +622,275 @@ where M: Map
               F: for<'a> FnOnce(BytesUninit<'a, T>) -> Bytes<'a, T>
     {
         let size = T::try_size(metadata).expect("valid metadata");
+        Ok(self.push_blob(size, |dst| {
+            let dst = BytesUninit::<T>::from_bytes(dst, metadata).expect("valid metadata");
+            f(dst);
+        }))
+    }
+}
 
+impl<'m, M: ?Sized> OffsetSaver<'m, M> {
+    fn push_blob(&mut self, size: usize, f: impl FnOnce(&mut [u8])) -> Offset {
         let old_len = self.dst.len();
         self.dst.resize(old_len + size, 0);
 
-        let dst = &mut self.dst[old_len ..];
-        let dst = BytesUninit::<T>::from_bytes(dst, metadata).expect("valid metadata");
+        f(&mut self.dst[old_len ..]);
+        Offset::new((self.base.unwrap_or(0) + old_len) as u64)
+    }
+}
+
+/// Object-safe destination for saved blobs.
+///
+/// `Saver` is generic over the values being saved, so it can't be made into a trait object.
+/// `DynSaver` erases the blob encoding behind a closure instead, allowing the destination to be
+/// chosen at runtime, eg. between an `OffsetSaver`, a `WriteSaver`, and a `DedupSaver`. Values
+/// are saved to a `DynSaver` with `save_dyn`.
+pub trait DynSaver {
+    /// Saves a blob of `size` bytes, encoded by `f`, returning its offset.
+    ///
+    /// `f` must be called exactly once, with a buffer of exactly `size` bytes.
+    fn save_blob_dyn(&mut self, size: usize, f: &mut dyn FnMut(&mut [u8])) -> Result<Offset, DynSaveError>;
+}
+
+/// Returned by a `DynSaver` when a blob can't be saved.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("failed to save blob: {source}"))]
+pub struct DynSaveError {
+    #[cfg_attr(feature = "std", source)]
+    pub source: Box<dyn error::Error + Send>,
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for DynSaveError {
+    fn from(err: io::Error) -> Self {
+        Self { source: Box::new(err) }
+    }
+}
+
+impl<'m, M: ?Sized> DynSaver for OffsetSaver<'m, M> {
+    fn save_blob_dyn(&mut self, size: usize, f: &mut dyn FnMut(&mut [u8])) -> Result<Offset, DynSaveError> {
+        Ok(self.push_blob(size, f))
+    }
+}
+
+/// Saver that writes blobs straight to a `Write`, rather than buffering them.
+///
+/// Offsets count from the start of the writer, or from `with_offset` if the writer is appending
+/// to an existing pile.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct WriteSaver<W> {
+    writer: W,
+    pos: u64,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> WriteSaver<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_offset(writer, 0)
+    }
+
+    pub fn with_offset(writer: W, pos: u64) -> Self {
+        Self { writer, pos, buf: vec![] }
+    }
+
+    /// Returns the offset the next blob will be written at.
+    pub fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> DynSaver for WriteSaver<W> {
+    fn save_blob_dyn(&mut self, size: usize, f: &mut dyn FnMut(&mut [u8])) -> Result<Offset, DynSaveError> {
+        self.buf.clear();
+        self.buf.resize(size, 0);
+        f(&mut self.buf);
+        self.writer.write_all(&self.buf)?;
+
+        let offset = Offset::new(self.pos);
+        self.pos += size as u64;
+        Ok(offset)
+    }
+}
+
+/// Saver that counts the bytes that would be saved, without keeping the blobs.
+///
+/// Blobs are still encoded, to a scratch buffer that's reused for every blob.
+#[derive(Debug, Default)]
+pub struct SizeSaver {
+    pos: u64,
+    buf: Vec<u8>,
+}
+
+impl SizeSaver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the total size of the blobs saved so far.
+    pub fn size(&self) -> u64 {
+        self.pos
+    }
+}
+
+impl DynSaver for SizeSaver {
+    fn save_blob_dyn(&mut self, size: usize, f: &mut dyn FnMut(&mut [u8])) -> Result<Offset, DynSaveError> {
+        self.buf.clear();
+        self.buf.resize(size, 0);
+        f(&mut self.buf);
+
+        let offset = Offset::new(self.pos);
+        self.pos += size as u64;
+        Ok(offset)
+    }
+}
+
+/// Saver that saves each distinct blob only once, passing the first copy on to an inner saver.
+///
+/// Blobs are compared by their bytes alone: a blob identical to one already saved gets the offset
+/// of that blob instead of being saved again. Every distinct blob is kept in memory to compare
+/// against.
+#[derive(Debug)]
+pub struct DedupSaver<S> {
+    inner: S,
+    saved: BTreeMap<Vec<u8>, Offset>,
+    buf: Vec<u8>,
+}
+
+impl<S> DedupSaver<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, saved: BTreeMap::new(), buf: vec![] }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: DynSaver> DynSaver for DedupSaver<S> {
+    fn save_blob_dyn(&mut self, size: usize, f: &mut dyn FnMut(&mut [u8])) -> Result<Offset, DynSaveError> {
+        self.buf.clear();
+        self.buf.resize(size, 0);
+        f(&mut self.buf);
+
+        if let Some(offset) = self.saved.get(&self.buf[..]) {
+            return Ok(*offset);
+        }
+
+        let blob = &self.buf;
+        let offset = self.inner.save_blob_dyn(size, &mut |dst| dst.copy_from_slice(blob))?;
+        self.saved.insert(self.buf.clone(), offset);
+        Ok(offset)
+    }
+}
+
+impl<S: ?Sized + DynSaver> DynSaver for Box<S> {
+    fn save_blob_dyn(&mut self, size: usize, f: &mut dyn FnMut(&mut [u8])) -> Result<Offset, DynSaveError> {
+        (**self).save_blob_dyn(size, f)
+    }
+}
+
+/// Saves `value` to a `DynSaver`, returning the offset of its blob.
+///
+/// Clean keys are loaded from `map` and copied, as with `OffsetSaver::new`.
+pub fn save_dyn<'m, M: ?Sized, T: ?Sized>(map: &'m M, saver: &mut dyn DynSaver, value: &T)
+    -> Result<Offset, Box<dyn error::Error>>
+    where M: Map<Key = Offset>,
+          T: SaveRef<Offset>,
+          Key<'m, M>: From<T::PtrClean>,
+          &'m M: AsZone<<T::PtrClean as PtrClean>::Zone>,
+{
+    let mut inner = DynBlobSaver { map, saver, reuse: false, progress: Progress::default() };
+    let wrapper: &mut Wrapper<DynBlobSaver<M>, T::PtrClean> = Wrapper::new(&mut inner);
+
+    let mut poll = value.init_save_ref();
+    wrapper.poll_ref::<T::SaveRefPoll>(&mut poll)
+}
+
+/// Saves the dirty parts of `value` to a `DynSaver`, returning the offset of its blob.
+///
+/// Unlike `save_dyn`, clean keys aren't copied: they're assumed to be offsets of blobs already
+/// saved to `saver`, and are reused as-is.
+pub fn save_dyn_dirty<'m, M: ?Sized, T: ?Sized>(map: &'m M, saver: &mut dyn DynSaver, value: &T)
+    -> Result<Offset, Box<dyn error::Error>>
+    where M: Map<Key = Offset>,
+          T: SaveRef<Offset>,
+          Key<'m, M>: From<T::PtrClean>,
+          &'m M: AsZone<<T::PtrClean as PtrClean>::Zone>,
+{
+    let mut inner = DynBlobSaver { map, saver, reuse: true, progress: Progress::default() };
+    let wrapper: &mut Wrapper<DynBlobSaver<M>, T::PtrClean> = Wrapper::new(&mut inner);
+
+    let mut poll = value.init_save_ref();
+    wrapper.poll_ref::<T::SaveRefPoll>(&mut poll)
+}
+
+struct DynBlobSaver<'s, 'm, M: ?Sized> {
+    map: &'m M,
+    saver: &'s mut dyn DynSaver,
+    reuse: bool,
+    progress: Progress,
+}
+
+impl<'s, 'm, M: ?Sized> BlobSaver for DynBlobSaver<'s, 'm, M>
+where M: Map<Key = Offset>
+{
+    type MapError = M::Error;
+    type SaveError = DynSaveError;
+
+    type Key = Key<'m, M>;
+
+    fn zone(&self) -> &<Self::Key as PtrClean>::Zone {
+        &self.map
+    }
+
+    fn key_offset(key: Self::Key) -> Option<Offset> {
+        Some(key.key)
+    }
 
-        f(dst);
-        Ok(Offset::new(old_len as u64))
+    fn progress_mut(&mut self) -> &mut Progress {
+        &mut self.progress
+    }
+
+    fn get_blob_with<T: ?Sized, F, R>(
+        &self,
+        key: Self::Key,
+        metadata: T::Metadata,
+        f: F,
+    ) -> Result<Result<Offset, R>, Self::MapError>
+        where T: BlobDyn,
+              F: FnOnce(Bytes<'_, T>) -> R
+    {
+        if self.reuse {
+            Ok(Ok(key.key))
+        } else {
+            let r = self.map.get_blob_with(key.key, metadata, f)?;
+            Ok(Err(r))
+        }
+    }
+
+    fn save_blob_with<T: ?Sized, F>(
+        &mut self,
+        metadata: T::Metadata,
+        f: F,
+    ) -> Result<Offset, Self::SaveError>
+        where T: BlobDyn,
+              F: for<'a> FnOnce(BytesUninit<'a, T>) -> Bytes<'a, T>
+    {
+        let size = T::try_size(metadata).expect("valid metadata");
+        let mut f = Some(f);
+        self.saver.save_blob_dyn(size, &mut |dst| {
+            let dst = BytesUninit::<T>::from_bytes(dst, metadata).expect("valid metadata");
+            let f = f.take().expect("blob encoded once");
+            f(dst);
+        })
     }
 }
 
@@ -237,6 +908,23 @@ impl<S, P> Wrapper<S, P> {
     }
 }
 
+impl<P: PtrClean, S: BlobSaver> Wrapper<S, P>
+where S::Key: From<P>,
+      <S::Key as PtrClean>::Zone: AsZone<P::Zone>
+{
+    fn context_error(&mut self, err: impl error::Error + Send + 'static, offset: Option<Offset>)
+        -> Box<dyn error::Error>
+    {
+        let progress = *self.progress_mut();
+        Box::new(SaveError {
+            blob: progress.blobs,
+            depth: progress.depth,
+            offset,
+            source: Box::new(err),
+        })
+    }
+}
+
 impl<P: PtrClean, S: BlobSaver> BlobSaver for Wrapper<S, P>
 where S::Key: From<P>,
       <S::Key as PtrClean>::Zone: AsZone<P::Zone>
@@ -250,6 +938,14 @@ where S::Key: From<P>,
         self.inner.zone().as_zone()
     }
 
+    fn key_offset(key: Self::Key) -> Option<Offset> {
+        S::key_offset(key.into())
+    }
+
+    fn progress_mut(&mut self) -> &mut Progress {
+        self.inner.progress_mut()
+    }
+
     fn get_blob_with<T: ?Sized, F, R>(
         &self,
         key: Self::Key,
@@ -278,7 +974,7 @@ impl<S: BlobSaver, P: PtrClean> Saver for Wrapper<S, P>
 where S::Key: From<P>,
       <S::Key as PtrClean>::Zone: AsZone<P::Zone>
 {
-    type Error = Box<dyn std::error::Error>;
+    type Error = Box<dyn error::Error>;
     type SrcPtr = P;
     type DstPtr = Offset;
 
@@ -291,14 +987,17 @@ where S::Key: From<P>,
         T: SaveRef<Offset>,
         <Self::SrcPtr as Ptr>::Zone: AsZone<T::Zone>,
     {
+        // A copied blob's own keys point into the same map it came from.
+        let zone = key.zone();
         let r = self.get_blob_with(key, metadata, |bytes| {
-            T::init_save_ref_from_bytes(bytes, self.zone().as_zone())
-        })?;
+            T::init_save_ref_from_bytes(bytes, zone.as_zone())
+        });
 
         match r {
-            Ok(offset) => Ok(Ok(offset)),
-            Err(Ok(poll)) => Ok(Err(poll)),
-            Err(Err(decode_err)) => Err(decode_err.into())
+            Ok(Ok(offset)) => Ok(Ok(offset)),
+            Ok(Err(Ok(poll))) => Ok(Err(poll)),
+            Ok(Err(Err(decode_err))) => Err(self.context_error(decode_err, Self::key_offset(key))),
+            Err(map_err) => Err(self.context_error(map_err, Self::key_offset(key))),
         }
     }
 
@@ -316,8 +1015,10 @@ where S::Key: From<P>,
               Self::SrcPtr: From<T::SrcPtr>,
               <Self::SrcPtr as Ptr>::Zone: AsZone<<T::SrcPtr as Ptr>::Zone>,
     {
+        self.progress_mut().depth += 1;
         let coerced: &mut Wrapper<Self, T::SrcPtr> = Wrapper::new(self);
         value.save_ref_poll(coerced)?;
+        self.progress_mut().depth -= 1;
 
         let offset = Saver::save_blob_with(self, value.blob_metadata(), |dst| {
             value.encode_blob_dyn_bytes(dst)
@@ -333,7 +1034,13 @@ where S::Key: From<P>,
         where T: BlobDyn,
               F: for<'a> FnOnce(BytesUninit<'a, T>) -> Bytes<'a, T>
     {
-        Ok(BlobSaver::save_blob_with(self, metadata, f)?)
+        match BlobSaver::save_blob_with(self, metadata, f) {
+            Ok(offset) => {
+                self.progress_mut().blobs += 1;
+                Ok(offset)
+            },
+            Err(err) => Err(self.context_error(err, None)),
+        }
     }
 }
 
@@ -347,6 +1054,60 @@ mod tests {
         key::KeyMut,
     };
     use crate::bag::Bag;
+    use crate::save::SaveRef;
+
+    use super::super::map::SliceError;
+
+    #[test]
+    fn offset_rebase() {
+        assert_eq!(Offset::new(10).rebase(5), Some(Offset::new(15)));
+        assert_eq!(Offset::new(10).rebase(-5), Some(Offset::new(5)));
+        assert_eq!(Offset::new(10).rebase(0), Some(Offset::new(10)));
+
+        // zero boundary
+        assert_eq!(Offset::new(10).rebase(-10), Some(Offset::new(0)));
+        assert_eq!(Offset::new(10).rebase(-11), None);
+        assert_eq!(Offset::new(0).rebase(i64::MIN), None);
+
+        // MAX boundary
+        assert_eq!(Offset::new(u64::MAX - 1).rebase(1), Some(Offset::MAX));
+        assert_eq!(Offset::MAX.rebase(1), None);
+        assert_eq!(Offset::MAX.rebase(i64::MIN), Some(Offset::new(u64::MAX / 2)));
+        assert_eq!(Offset::new(0).rebase(i64::MAX), Some(Offset::new(i64::MAX as u64)));
+    }
+
+    #[test]
+    fn offset_ord() {
+        assert!(Offset::new(1) < Offset::new(2));
+        assert_eq!(Offset::new(1).cmp_position(Offset::new(2)), cmp::Ordering::Less);
+        assert_eq!(Offset::new(2).cmp_position(Offset::new(2)), cmp::Ordering::Equal);
+        assert_eq!(Offset::MAX.cmp_position(Offset::new(0)), cmp::Ordering::Greater);
+
+        let mut offsets = vec![Offset::new(17), Offset::MAX, Offset::new(0), Offset::new(9)];
+        offsets.sort();
+        assert_eq!(offsets, vec![Offset::new(0), Offset::new(9), Offset::new(17), Offset::MAX]);
+    }
+
+    #[test]
+    fn offset_region() {
+        assert_eq!(Offset::new(0).region(0), 0 .. 0);
+        assert_eq!(Offset::new(3).region(8), 3 .. 11);
+
+        // adjacent
+        assert!(!regions_overlap(Offset::new(0).region(8), Offset::new(8).region(8)));
+        assert!(!regions_overlap(Offset::new(8).region(8), Offset::new(0).region(8)));
+
+        // overlapping
+        assert!(regions_overlap(Offset::new(0).region(8), Offset::new(7).region(8)));
+        assert!(regions_overlap(Offset::new(0).region(8), Offset::new(2).region(2)));
+        assert!(regions_overlap(Offset::new(2).region(2), Offset::new(0).region(8)));
+
+        // disjoint
+        assert!(!regions_overlap(Offset::new(0).region(4), Offset::new(10).region(4)));
+
+        // empty
+        assert!(!regions_overlap(Offset::new(4).region(0), Offset::new(0).region(8)));
+    }
 
     #[test]
     fn offset_saver_u8() {
@@ -392,4 +1153,376 @@ mod tests {
             9,0,0,0,0,0,0,0,
         ]);
     }
+
+    #[test]
+    fn offset_saver_with_base() {
+        let bag = Heap::alloc(Heap::alloc(32u8));
+        let saver = OffsetSaver::new(&[][..]);
+        let (_, buf) = saver.try_save(&bag).unwrap();
+        assert_eq!(buf.len(), 17);
+
+        // New blobs are offset past the base
+        let base: &[u8] = &buf;
+        let bag = Heap::alloc(Heap::alloc(33u8));
+        let saver = OffsetSaver::with_base(base);
+        let (offset, new) = saver.try_save(&bag).unwrap();
+        assert_eq!(offset, 17 + 9);
+        assert_eq!(new, &[
+            33,
+            17,0,0,0,0,0,0,0,
+            18,0,0,0,0,0,0,0,
+        ]);
+
+        // Clean keys into the base are reused; only the root itself is written.
+        let key = Key::<[u8]>::from_blob(Offset::new(1), &base);
+        let loaded: Bag<Bag<u8, Key<[u8]>>, Key<[u8]>> = unsafe { Bag::from_raw_parts(key, ()) };
+
+        let saver = OffsetSaver::with_base(base);
+        let (offset, new) = saver.try_save(&loaded).unwrap();
+        assert_eq!(offset, 17);
+        assert_eq!(new, &[1,0,0,0,0,0,0,0]);
+
+        // Whereas without a base everything is copied.
+        let saver = OffsetSaver::new(base);
+        let (offset, new) = saver.try_save(&loaded).unwrap();
+        assert_eq!(offset, 9);
+        assert_eq!(new.len(), 17);
+
+        // Keys into a different pile aren't in the base, so they're copied too.
+        let other = buf.clone();
+        let other: &[u8] = &other;
+        let key = Key::<[u8]>::from_blob(Offset::new(1), &other);
+        let foreign: Bag<Bag<u8, Key<[u8]>>, Key<[u8]>> = unsafe { Bag::from_raw_parts(key, ()) };
+
+        let saver = OffsetSaver::with_base(base);
+        let (offset, new) = saver.try_save(&foreign).unwrap();
+        assert_eq!(offset, 17 + 9);
+        assert_eq!(new, &[
+            32,
+            17,0,0,0,0,0,0,0,
+            18,0,0,0,0,0,0,0,
+        ]);
+    }
+
+    #[test]
+    fn dirty_offset_saver_reuses_clean_keys() {
+        let bag = Heap::alloc(Heap::alloc(32u8));
+        let saver = OffsetSaver::new(&[][..]);
+        let (_, buf) = saver.try_save(&bag).unwrap();
+
+        let map: &[u8] = &buf;
+        let key = Key::<[u8]>::from_blob(Offset::new(1), &map);
+        let loaded: Bag<Bag<u8, Key<[u8]>>, Key<[u8]>> = unsafe { Bag::from_raw_parts(key, ()) };
+
+        let saver = DirtyOffsetSaver::new(map);
+        let (offset, new) = saver.try_save(&loaded).unwrap();
+        assert_eq!(offset, 17);
+        assert_eq!(new, &[1,0,0,0,0,0,0,0]);
+    }
+
+    #[test]
+    fn offset_saver_finish() {
+        let map: &[u8] = &[];
+        let mut saver = OffsetSaver::new(map);
+
+        let root1 = saver.save(&Heap::alloc(Heap::alloc(1u8))).unwrap();
+        let root2 = saver.save(&Heap::alloc(2u16)).unwrap();
+        let buf = saver.finish();
+        assert_eq!(root1, 9);
+        assert_eq!(root2, 19);
+        assert_eq!(buf.len(), 27);
+
+        let map: &[u8] = &buf;
+        let key = Key::<[u8]>::from_blob(root1, &map);
+        let r = unsafe { key.try_get::<Bag<Bag<u8, Key<[u8]>>, Key<[u8]>>>(()).unwrap().trust() };
+        assert_eq!(*r.get().get(), 1);
+
+        let key = Key::<[u8]>::from_blob(root2, &map);
+        let r = unsafe { key.try_get::<Bag<u16, Key<[u8]>>>(()).unwrap().trust() };
+        assert_eq!(*r.get(), 2);
+    }
+
+    #[test]
+    fn finish_with_index() {
+        let map: &[u8] = &[];
+        let mut saver = OffsetSaver::new(map);
+
+        let root1 = saver.save(&Heap::alloc(Heap::alloc(1u8))).unwrap();
+        let root2 = saver.save(&Heap::alloc(2u16)).unwrap();
+        let buf = saver.finish_with_index(&[("nested", root1), ("u16", root2)]);
+
+        assert_eq!(read_index(&buf).unwrap(), vec![
+            (String::from("nested"), root1),
+            (String::from("u16"), root2),
+        ]);
+
+        // Blobs are still loadable at their offsets
+        let map: &[u8] = &buf;
+        let key = Key::<[u8]>::from_blob(root2, &map);
+        let r = unsafe { key.try_get::<Bag<u16, Key<[u8]>>>(()).unwrap().trust() };
+        assert_eq!(*r.get(), 2);
+
+        let empty = OffsetSaver::new(map).finish_with_index(&[]);
+        assert_eq!(read_index(&empty).unwrap(), vec![]);
+
+        assert_eq!(read_index(&[]), Err(ReadIndexError::Truncated));
+        let mut bad_len = buf.clone();
+        let n = bad_len.len();
+        bad_len[n - 8] += 1;
+        assert!(read_index(&bad_len).is_err());
+        assert_eq!(read_index(&buf[.. buf.len() - 1]), Err(ReadIndexError::Truncated));
+    }
+
+    #[test]
+    fn save_dyn() {
+        let map: &[u8] = &[];
+        let bag = Heap::alloc(Heap::alloc(Heap::alloc(32u8)));
+        let (expected_offset, expected) = OffsetSaver::new(map).try_save(&bag).unwrap();
+
+        for use_writer in [false, true].iter() {
+            let mut written = vec![];
+            let mut saver: Box<dyn DynSaver + '_> = if *use_writer {
+                Box::new(WriteSaver::new(&mut written))
+            } else {
+                Box::new(OffsetSaver::new(map))
+            };
+
+            let offset = super::save_dyn(map, &mut *saver, &bag).unwrap();
+            assert_eq!(offset, expected_offset);
+            drop(saver);
+
+            if *use_writer {
+                assert_eq!(written, expected);
+            }
+        }
+
+        let mut saver = SizeSaver::new();
+        assert_eq!(super::save_dyn(map, &mut saver, &bag).unwrap(), expected_offset);
+        assert_eq!(saver.size(), expected.len() as u64);
+
+        // Identical blobs are only saved once
+        let mut saver = DedupSaver::new(SizeSaver::new());
+        assert_eq!(super::save_dyn(map, &mut saver, &bag).unwrap(), expected_offset);
+        assert_eq!(super::save_dyn(map, &mut saver, &bag).unwrap(), expected_offset);
+        let other = Heap::alloc(Heap::alloc(Heap::alloc(33u8)));
+        assert_eq!(super::save_dyn(map, &mut saver, &other).unwrap(), expected_offset.get() + expected.len() as u64);
+        assert_eq!(saver.into_inner().size(), expected.len() as u64 * 2);
+
+        // Clean keys are copied from the map
+        let map: &[u8] = &expected;
+        let key = Key::<[u8]>::from_blob(Offset::new(1), &map);
+        let loaded: Bag<Bag<u8, Key<[u8]>>, Key<[u8]>> = unsafe { Bag::from_raw_parts(key, ()) };
+
+        let mut saver = WriteSaver::with_offset(vec![], 100);
+        let offset = super::save_dyn(map, &mut saver, &loaded).unwrap();
+        assert_eq!(offset, 109);
+        assert_eq!(saver.pos(), 117);
+        assert_eq!(saver.into_inner(), &[
+            32,
+            100,0,0,0,0,0,0,0,
+            101,0,0,0,0,0,0,0,
+        ]);
+
+        // ...unless they're assumed to already be saved
+        let mut saver = WriteSaver::with_offset(vec![], 100);
+        assert_eq!(super::save_dyn_dirty(map, &mut saver, &loaded).unwrap(), 100);
+        assert_eq!(saver.into_inner(), &[1,0,0,0,0,0,0,0]);
+
+        let dirty = Heap::alloc(Heap::alloc(7u8));
+        let mut saver = WriteSaver::with_offset(vec![], 100);
+        assert_eq!(super::save_dyn_dirty(map, &mut saver, &dirty).unwrap(), 109);
+        assert_eq!(saver.into_inner(), &[
+            7,
+            100,0,0,0,0,0,0,0,
+            101,0,0,0,0,0,0,0,
+        ]);
+    }
+
+    #[test]
+    fn save_new_appends() {
+        let mut pile = vec![];
+        let root1 = save_new(&mut pile, &Heap::alloc(Heap::alloc(1u8))).unwrap();
+        assert_eq!(root1, 9);
+        assert_eq!(pile.len(), 17);
+
+        let root2 = save_new(&mut pile, &Heap::alloc(2u16)).unwrap();
+        assert_eq!(root2, 19);
+        assert_eq!(pile.len(), 27);
+
+        let map: &[u8] = &pile;
+        let key = Key::<[u8]>::from_blob(root1, &map);
+        let r = unsafe { key.try_get::<Bag<Bag<u8, Key<[u8]>>, Key<[u8]>>>(()).unwrap().trust() };
+        assert_eq!(*r.get().get(), 1);
+
+        let key = Key::<[u8]>::from_blob(root2, &map);
+        let r = unsafe { key.try_get::<Bag<u16, Key<[u8]>>>(()).unwrap().trust() };
+        assert_eq!(*r.get(), 2);
+    }
+
+    #[test]
+    fn compact_drops_garbage() {
+        let mut pile = vec![];
+        let stale = save_new(&mut pile, &Heap::alloc(Heap::alloc(1u8))).unwrap();
+        let root = save_new(&mut pile, &Heap::alloc(Heap::alloc(2u8))).unwrap();
+        assert_eq!(stale, 9);
+        assert_eq!(root, 26);
+        assert_eq!(pile.len(), 34);
+
+        let (compacted, new_root) = compact::<Bag<Bag<u8, Key<[u8]>>, Key<[u8]>>>(&pile, root).unwrap();
+        assert_eq!(new_root, 9);
+        assert_eq!(compacted.len(), 17);
+
+        let map: &[u8] = &compacted;
+        let key = Key::<[u8]>::from_blob(new_root, &map);
+        let r = unsafe { key.try_get::<Bag<Bag<u8, Key<[u8]>>, Key<[u8]>>>(()).unwrap().trust() };
+        assert_eq!(*r.get().get(), 2);
+
+        // Out of bounds roots are errors
+        assert!(compact::<Bag<u8, Key<[u8]>>>(&pile, Offset::new(34)).is_err());
+    }
+
+    #[derive(Debug, Error)]
+    #[error("flaky storage")]
+    struct FlakyError;
+
+    struct FlakySaver<'m> {
+        inner: OffsetSaver<'m, [u8]>,
+        fail_at: usize,
+        saved: usize,
+    }
+
+    impl<'m> BlobSaver for FlakySaver<'m> {
+        type MapError = SliceError;
+        type SaveError = FlakyError;
+
+        type Key = Key<'m, [u8]>;
+
+        fn zone(&self) -> &&'m [u8] {
+            self.inner.zone()
+        }
+
+        fn key_offset(key: Self::Key) -> Option<Offset> {
+            OffsetSaver::<[u8]>::key_offset(key)
+        }
+
+        fn progress_mut(&mut self) -> &mut Progress {
+            self.inner.progress_mut()
+        }
+
+        fn get_blob_with<T: ?Sized, F, R>(&self, key: Self::Key, metadata: T::Metadata, f: F)
+            -> Result<Result<Offset, R>, SliceError>
+            where T: BlobDyn,
+                  F: FnOnce(Bytes<'_, T>) -> R
+        {
+            self.inner.get_blob_with(key, metadata, f)
+        }
+
+        fn save_blob_with<T: ?Sized, F>(&mut self, metadata: T::Metadata, f: F) -> Result<Offset, FlakyError>
+            where T: BlobDyn,
+                  F: for<'a> FnOnce(BytesUninit<'a, T>) -> Bytes<'a, T>
+        {
+            if self.saved == self.fail_at {
+                Err(FlakyError)
+            } else {
+                self.saved += 1;
+                Ok(self.inner.save_blob_with(metadata, f).into_ok())
+            }
+        }
+    }
+
+    #[test]
+    fn save_error_context() {
+        let map: &[u8] = &[];
+        let bag = Heap::alloc(Heap::alloc(Heap::alloc(32u8)));
+
+        for fail_at in 0 .. 4 {
+            let mut saver = FlakySaver { inner: OffsetSaver::new(map), fail_at, saved: 0 };
+            let wrapper: &mut Wrapper<FlakySaver, <Heap as Ptr>::Clean> = Wrapper::new(&mut saver);
+            let mut poll = bag.init_save_ref();
+
+            let err = wrapper.poll_ref(&mut poll).unwrap_err();
+            let err = err.downcast::<SaveError>().unwrap();
+            assert_eq!(err.blob, fail_at);
+            assert_eq!(err.depth, 3 - fail_at);
+            assert_eq!(err.offset, None);
+            assert!(err.source.is::<FlakyError>());
+        }
+
+        // Truncated clean blobs report their offset
+        let (_, buf) = OffsetSaver::new(map).try_save(&bag).unwrap();
+        let truncated: &[u8] = &buf[.. 9];
+        let key = Key::<[u8]>::from_blob(Offset::new(9), &truncated);
+        let loaded: Bag<Bag<u8, Key<[u8]>>, Key<[u8]>> = unsafe { Bag::from_raw_parts(key, ()) };
+
+        let err = OffsetSaver::new(truncated).try_save(&loaded).unwrap_err();
+        let err = err.downcast::<SaveError>().unwrap();
+        assert_eq!(err.blob, 0);
+        assert_eq!(err.offset, Some(Offset::new(9)));
+        assert!(err.source.is::<SliceError>());
+
+        // Blobs are counted per save
+        let mut saver = OffsetSaver::new(truncated);
+        saver.save(&Heap::alloc(1u8)).unwrap();
+        let err = saver.save(&loaded).unwrap_err();
+        assert_eq!(err.downcast::<SaveError>().unwrap().blob, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn file_round_trip() {
+        use std::io::Cursor;
+
+        let map: &[u8] = &[];
+        let bag = Heap::alloc(Heap::alloc(32u8));
+
+        let mut saver = OffsetSaver::new(map);
+        let root = saver.save(&bag).unwrap();
+
+        let mut file = Cursor::new(vec![]);
+        saver.into_file(root, &mut file).unwrap();
+        assert_eq!(file.get_ref().len(), FileHeader::SIZE + 17);
+        assert_eq!(&file.get_ref()[0 .. 4], b"hord");
+
+        file.set_position(0);
+        let (root, pile) = load_from_file(&mut file).unwrap();
+        assert_eq!(root, 9);
+        assert_eq!(pile, &[
+            32,
+            0,0,0,0,0,0,0,0,
+            1,0,0,0,0,0,0,0,
+        ]);
+
+        let mut bad = file.into_inner();
+        bad[0] = b'x';
+        assert!(matches!(load_from_file(Cursor::new(&bad)), Err(LoadFileError::Magic)));
+
+        bad[0] = b'h';
+        bad[4] = 1;
+        assert!(matches!(load_from_file(Cursor::new(&bad)), Err(LoadFileError::Version(1))));
+
+        assert!(matches!(load_from_file(Cursor::new(&bad[0 .. 8])), Err(LoadFileError::Io(_))));
+    }
+
+    #[test]
+    fn empty_pile() {
+        let (offset, buf) = OffsetSaver::empty().try_save(&42u8).unwrap();
+        assert_eq!(offset, 0);
+        assert_eq!(buf, &[42]);
+
+        // Nothing can be loaded from an empty pile
+        let err = EMPTY_PILE.read_txn().get::<u8>(Offset::new(0)).unwrap_err();
+        assert!(matches!(err.kind(), crate::ptr::ErrorKind::Zone(SliceError::OutOfBounds { .. })));
+        assert_eq!(EMPTY_PILE.get_blob_with::<u8, _, _>(Offset::new(0), (), |_| ()).unwrap_err().to_string(),
+                   "blob at offset Offset(0) of size 1 out of bounds");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn load_empty_file() {
+        use std::io::Cursor;
+
+        let err = load_from_file(Cursor::new(EMPTY_PILE)).unwrap_err();
+        assert!(matches!(err, LoadFileError::Empty));
+        assert_eq!(err.to_string(), "empty file, missing pile header");
+    }
 }
@@ -0,0 +1,86 @@
+//! Maps that record which blobs were loaded.
+
+use core::cell::RefCell;
+use core::mem;
+
+use alloc::{vec, vec::Vec};
+
+use crate::blob::{BlobDyn, Bytes};
+
+use super::Map;
+
+/// Wraps a `Map`, recording the key of every blob loaded through it.
+///
+/// Loading through `Key<TracingZone<M>>` records exactly the blobs needed to repeat an operation,
+/// eg. the root-to-leaf path of a tree `get`.
+#[derive(Debug)]
+pub struct TracingZone<M: Map> {
+    trace: RefCell<Vec<M::Key>>,
+    inner: M,
+}
+
+impl<M: Map> TracingZone<M> {
+    pub fn new(inner: M) -> Self {
+        Self {
+            trace: RefCell::new(vec![]),
+            inner,
+        }
+    }
+
+    /// Returns the keys loaded so far, clearing the trace.
+    pub fn take_trace(&self) -> Vec<M::Key> {
+        mem::take(&mut *self.trace.borrow_mut())
+    }
+
+    /// Returns the keys loaded, in order.
+    pub fn into_trace(self) -> Vec<M::Key> {
+        self.trace.into_inner()
+    }
+}
+
+impl<M: Map> Map for TracingZone<M> {
+    type Id = M::Id;
+    type Error = M::Error;
+    type Key = M::Key;
+
+    #[inline]
+    fn id(&self) -> Self::Id {
+        self.inner.id()
+    }
+
+    fn get_blob_with<T: ?Sized, F, R>(&self, key: Self::Key, metadata: T::Metadata, f: F) -> Result<R, Self::Error>
+        where F: FnOnce(Bytes<T>) -> R,
+              T: BlobDyn
+    {
+        self.trace.borrow_mut().push(key);
+        self.inner.get_blob_with(key, metadata, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ptr::{PtrClean, TryGet};
+    use crate::ptr::key::{Key, Offset};
+
+    #[test]
+    fn trace() {
+        let buf: &[u8] = &[0x12, 0x34, 0x56];
+        let zone = TracingZone::new(buf);
+
+        buf.get_blob_with::<u8, _, _>(Offset::new(0), (), |_| ()).unwrap();
+        zone.get_blob_with::<u16, _, _>(Offset::new(1), (), |_| ()).unwrap();
+        assert_eq!(zone.take_trace(), vec![Offset::new(1)]);
+
+        let zone_ref = &zone;
+        let key = Key::from_blob(Offset::new(2), &zone_ref);
+        let r = unsafe { key.try_get::<u8>(()).unwrap().trust() };
+        assert_eq!(*r, 0x56);
+
+        let key = Key::from_blob(Offset::new(3), &zone_ref);
+        assert!(unsafe { key.try_get::<u8>(()) }.is_err());
+
+        assert_eq!(zone.into_trace(), vec![Offset::new(2), Offset::new(3)]);
+    }
+}
@@ -0,0 +1,121 @@
+//! Scoped reads from a map.
+
+use crate::load::{Load, MaybeValid};
+
+use super::*;
+
+/// A read transaction, borrowing a map immutably for its duration.
+///
+/// Values loaded with `get` have pointers that borrow the map for its lifetime `'m`, so the map
+/// can't be mutated or dropped while any of them are still in use. `Ref`s returned by `get_ref`
+/// and `get_leaf` borrow the transaction itself, so they can't outlive it:
+///
+/// ```
+/// use hoard::prelude::*;
+/// use hoard::ptr::key::{Map, Offset};
+///
+/// let pile = vec![42u8, 0, 0, 0, 0, 0, 0, 0, 0];
+///
+/// let txn = pile[..].read_txn();
+/// let bag: Bag<u8, Key<[u8]>> = txn.get(Offset::new(1)).unwrap().trust();
+/// assert_eq!(*bag.get(), 42);
+///
+/// let r = txn.get_leaf::<u8>(Offset::new(0)).unwrap().trust();
+/// assert_eq!(*r, 42);
+/// ```
+///
+/// Whereas letting a `Ref` outlive its transaction is a compile-time error, even though the map
+/// is still around:
+///
+/// ```compile_fail
+/// use hoard::ptr::key::{Map, Offset};
+///
+/// let pile = vec![42u8];
+/// let r = {
+///     let txn = pile[..].read_txn();
+///     txn.get_leaf::<u8>(Offset::new(0)).unwrap().trust()
+/// };
+/// assert_eq!(*r, 42);
+/// ```
+#[derive(Debug)]
+pub struct ReadTxn<'m, M: ?Sized> {
+    map: &'m M,
+}
+
+/// Returned by `ReadTxn::get_ref` and `ReadTxn::get_leaf`.
+pub type GetRefResult<'t, T, M> = Result<MaybeValid<Ref<'t, T>>, Error<<M as Map>::Id, <M as Map>::Error>>;
+
+impl<'m, M: ?Sized + Map> ReadTxn<'m, M> {
+    pub fn new(map: &'m M) -> Self {
+        Self { map }
+    }
+
+    pub fn map(&self) -> &'m M {
+        self.map
+    }
+
+    /// Returns a clean key for `key`, valid for the duration of the transaction.
+    pub fn key(&self, key: M::Key) -> Key<'m, M> {
+        Key::from_blob(key, &self.map)
+    }
+
+    /// Loads the `T` at `key`.
+    ///
+    /// Any pointers in the returned value borrow the map for the duration of the transaction.
+    pub fn get<T: Load>(&self, key: M::Key) -> Result<MaybeValid<T>, Error<M::Id, M::Error>>
+        where &'m M: AsZone<T::Zone>
+    {
+        // SAFETY: sized types have no metadata to get wrong
+        unsafe { self.key(key).try_take::<T>(()) }
+    }
+
+    /// Loads the `T` at `key` as a `Ref` borrowing the transaction.
+    ///
+    /// The `Ref` is currently always owned; borrowing the transaction leaves room for returning
+    /// values decoded in place.
+    pub fn get_ref<'t, T: Load>(&'t self, key: M::Key) -> GetRefResult<'t, T, M>
+        where &'m M: AsZone<T::Zone>
+    {
+        self.get::<T>(key).map(|value| MaybeValid::from(Ref::Owned(value.trust())))
+    }
+
+    /// Loads a leaf value, one without pointers, as a `Ref` borrowing the transaction.
+    pub fn get_leaf<'t, T>(&'t self, key: M::Key) -> GetRefResult<'t, T, M>
+        where T: Load<Zone = ()>
+    {
+        self.get_ref::<T>(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bag::Bag;
+
+    #[test]
+    fn read_txn() {
+        let pile = vec![
+            42,
+            0,0,0,0,0,0,0,0,
+            1,0,0,0,0,0,0,0,
+        ];
+
+        let txn = pile[..].read_txn();
+        assert_eq!(txn.get::<u8>(Offset::new(0)).unwrap().trust(), 42);
+
+        let bag = txn.get::<Bag<Bag<u8, Key<[u8]>>, Key<[u8]>>>(Offset::new(9)).unwrap().trust();
+        assert_eq!(*bag.get().get(), 42);
+        assert!(core::ptr::eq(bag.ptr().zone(), txn.map()));
+
+        assert!(txn.get::<u64>(Offset::new(10)).is_err());
+
+        let r = txn.get_ref::<u8>(Offset::new(0)).unwrap().trust();
+        assert_eq!(*r, 42);
+        assert!(txn.get_ref::<u64>(Offset::new(10)).is_err());
+
+        let r = txn.get_leaf::<u64>(Offset::new(9)).unwrap().trust();
+        assert_eq!(*r, 1);
+        assert!(txn.get_leaf::<u64>(Offset::new(10)).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 //! Copy-on-write pointer functionality, for data that may be stored on disk.
 
-use std::fmt;
+use core::fmt;
+
+use alloc::vec::Vec;
 
 use crate::blob::Blob;
 use crate::pointee::Pointee;
@@ -74,6 +76,29 @@ pub trait Ptr : Sized {
     }
 }
 
+/// Zones that can allocate new values.
+pub trait Alloc {
+    type Ptr : Ptr;
+
+    /// Returned when the zone can't allocate, eg. because it's out of space.
+    type Error : fmt::Debug;
+
+    fn try_alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Result<Bag<T, Self::Ptr>, Self::Error>;
+
+    /// Allocates a value, panicking if the zone can't allocate.
+    #[track_caller]
+    fn alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Bag<T, Self::Ptr> {
+        self.try_alloc(src).expect("allocation failed")
+    }
+
+    /// Allocates a slice directly from an iterator of items.
+    fn alloc_slice<T>(&mut self, items: impl ExactSizeIterator<Item = T>) -> Bag<[T], Self::Ptr> {
+        let mut v = Vec::with_capacity(items.len());
+        v.extend(items);
+        self.alloc::<[T]>(v)
+    }
+}
+
 /// Needs no deallocation; data available.
 pub trait PtrClean : Copy {
     type Zone : Zone;
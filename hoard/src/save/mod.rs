@@ -1,6 +1,6 @@
 //! Saving data in zones.
 
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 use crate::load::*;
 use crate::blob::*;
@@ -65,6 +65,13 @@ pub trait SavePoll {
 
     fn encode_blob(&self) -> Self::DstBlob;
 
+    /// Best-effort estimate of the number of blobs left to save, for progress reporting.
+    ///
+    /// Doesn't include the blob for `Self`. Defaults to zero.
+    fn blobs_remaining(&self) -> usize {
+        0
+    }
+
     fn encode_blob_bytes<'a>(&self, dst: BytesUninit<'a, Self::DstBlob>) -> Bytes<'a, Self::DstBlob> {
         let blob = self.encode_blob();
         Blob::encode_bytes(&blob, dst)
@@ -96,6 +103,14 @@ pub trait SaveRefPoll {
 
     fn blob_metadata(&self) -> <Self::DstBlob as Pointee>::Metadata;
     fn encode_blob_dyn_bytes<'a>(&self, dst: BytesUninit<'a, Self::DstBlob>) -> Bytes<'a, Self::DstBlob>;
+
+    /// Best-effort estimate of the number of blobs left to save, for progress reporting.
+    ///
+    /// Same as `SavePoll::blobs_remaining`, which sized types forward to. Doesn't include the blob
+    /// for `Self`. Defaults to zero.
+    fn ref_blobs_remaining(&self) -> usize {
+        0
+    }
 }
 
 impl<Q, T: Save<Q>> SaveRef<Q> for T {
@@ -130,4 +145,8 @@ impl<T: SavePoll> SaveRefPoll for T {
     fn encode_blob_dyn_bytes<'a>(&self, dst: BytesUninit<'a, Self::DstBlob>) -> Bytes<'a, Self::DstBlob> {
         self.encode_blob_bytes(dst)
     }
+
+    fn ref_blobs_remaining(&self) -> usize {
+        self.blobs_remaining()
+    }
 }
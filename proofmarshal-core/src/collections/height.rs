@@ -14,13 +14,13 @@ use crate::unreachable_unchecked;
 
 /// The height of a perfect binary tree.
 ///
-/// Valid range: `0 ..= 63`
+/// Valid range: `0 ..= Height::MAX`, which is 63 on 64-bit targets.
 #[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Height(u8);
 
 /// The height of an inner node in a perfect binary tree.
 ///
-/// Valid range: `1 ..= 63`
+/// Valid range: `1 ..= Height::MAX`, which is 63 on 64-bit targets.
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct NonZeroHeight(NonZeroU8);
 
@@ -128,17 +128,28 @@ impl TryFrom<usize> for NonZeroHeight {
 }
 
 impl Height {
-    pub const MAX: u8 = 63;
     pub const ZERO: Self = unsafe { Self::new_unchecked(0) };
+    pub const MIN: Self = Self::ZERO;
+
+    /// The largest possible height, one less than the number of bits in a `usize`.
+    ///
+    /// Chosen so that a tree of every valid height has a length that fits in a `usize`: lengths
+    /// are computed as `1 << height` without overflow checks. This makes the limit depend on the
+    /// target, eg. 63 on 64-bit and 31 on 32-bit.
+    ///
+    /// Heights are validated against this limit when decoded, so the on-disk format is
+    /// target-dependent too: a pile containing a tree taller than 31 decodes on 64-bit targets but
+    /// is rejected as invalid on 32-bit targets.
+    pub const MAX: Self = unsafe { Self::new_unchecked((usize::BITS - 1) as u8) };
 
     #[inline(always)]
     fn assert_valid(&self) {
-        debug_assert!(self.0 <= Self::MAX);
+        debug_assert!(self.0 <= Self::MAX.0);
     }
 
     #[inline(always)]
     pub fn new(n: u8) -> Option<Self> {
-        if n <= Self::MAX {
+        if n <= Self::MAX.0 {
             Some(Self(n))
         } else {
             None
@@ -149,6 +160,14 @@ impl Height {
         Self(n)
     }
 
+    /// Creates a `Height` from a `u8`, returning `None` if out of range.
+    ///
+    /// Equivalent to `new`, provided for symmetry with `NonZeroHeight::from_u8`.
+    #[inline(always)]
+    pub fn from_u8(n: u8) -> Option<Self> {
+        Self::new(n)
+    }
+
     #[inline(always)]
     pub const fn get(self) -> u8 {
         self.0
@@ -160,28 +179,49 @@ impl Height {
         1 << self.0
     }
 
+    /// Returns the height of a perfect tree containing `len` items.
+    ///
+    /// Returns `None` if `len` is not a power of two.
+    ///
+    /// ```
+    /// # use proofmarshal_core::collections::height::Height;
+    /// assert_eq!(Height::try_from_len(8), Height::new(3));
+    /// assert_eq!(Height::try_from_len(6), None);
+    /// ```
+    #[inline]
+    pub fn try_from_len(len: usize) -> Option<Self> {
+        if len.is_power_of_two() {
+            Self::new(len.trailing_zeros() as u8)
+        } else {
+            None
+        }
+    }
+
     #[inline]
     pub fn try_increment(self) -> Option<NonZeroHeight> {
-        if self.0 < Self::MAX {
+        if self.0 < Self::MAX.0 {
             let n = unsafe { NonZeroU8::new_unchecked(self.0 + 1) };
             let n = unsafe { NonZeroHeight::new_unchecked(n) };
             Some(n)
         } else {
-            assert!(self.0 == Self::MAX);
+            assert!(self.0 == Self::MAX.0);
             None
         }
     }
 }
 
 impl NonZeroHeight {
+    pub const MIN: Self = unsafe { Self::new_unchecked(NonZeroU8::new_unchecked(1)) };
+    pub const MAX: Self = unsafe { Self::new_unchecked(NonZeroU8::new_unchecked(Height::MAX.get())) };
+
     #[inline(always)]
     fn assert_valid(&self) {
-        debug_assert!(self.0.get() <= Height::MAX);
+        debug_assert!(self.0 <= Self::MAX.0);
     }
 
     #[inline(always)]
     pub fn new(n: NonZeroU8) -> Option<Self> {
-        if n.get() <= Height::MAX {
+        if n <= Self::MAX.0 {
             Some(Self(n))
         } else {
             None
@@ -197,10 +237,24 @@ impl NonZeroHeight {
         Self(n)
     }
 
+    /// Creates a `NonZeroHeight` from a `u8`, returning `None` if zero or out of range.
+    #[inline(always)]
+    pub fn from_u8(n: u8) -> Option<Self> {
+        NonZeroU8::new(n).and_then(Self::new)
+    }
+
     pub fn decrement(self) -> Height {
         Height::new(self.0.get() - 1)
                .unwrap_or_else(|| unsafe { unreachable_unchecked!() })
     }
+
+    /// Returns an iterator over every height from `self` down to zero, inclusive.
+    #[inline]
+    pub fn descend(self) -> impl Iterator<Item = Height> + DoubleEndedIterator + ExactSizeIterator {
+        self.assert_valid();
+        (0 ..= self.0.get()).rev()
+                            .map(|n| unsafe { Height::new_unchecked(n) })
+    }
 }
 
 impl ToHeight for Height {
@@ -218,7 +272,7 @@ impl ToNonZeroHeight for NonZeroHeight {
 impl ToHeight for HeightDyn {
     fn to_height(&self) -> Height {
         let n = self.0.len();
-        debug_assert!(n < Height::MAX as usize);
+        debug_assert!(n <= Height::MAX.get() as usize);
         unsafe { Height::new_unchecked(n as u8) }
     }
 }
@@ -226,7 +280,7 @@ impl ToHeight for HeightDyn {
 impl ToNonZeroHeight for NonZeroHeightDyn {
     fn to_nonzero_height(&self) -> NonZeroHeight {
         let n = self.0.len();
-        debug_assert!(n < Height::MAX as usize);
+        debug_assert!(n <= Height::MAX.get() as usize);
         debug_assert!(n != 0);
         unsafe {
             NonZeroHeight::new_unchecked(NonZeroU8::new_unchecked(n as u8))
@@ -420,3 +474,84 @@ impl_fmt! {
     Height, HeightDyn,
     NonZeroHeight, NonZeroHeightDyn,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonzero_height_descend() {
+        let height = NonZeroHeight::try_from(3usize).unwrap();
+        let heights: Vec<u8> = height.descend().map(Height::get).collect();
+        assert_eq!(heights, &[3, 2, 1, 0]);
+
+        let height = NonZeroHeight::try_from(1usize).unwrap();
+        assert_eq!(height.descend().len(), 2);
+    }
+
+    #[test]
+    fn height_min_max() {
+        assert_eq!(Height::MIN, 0u8);
+        assert_eq!(Height::MAX.get() as u32, usize::BITS - 1);
+        assert_eq!(NonZeroHeight::MIN, 1u8);
+        assert_eq!(NonZeroHeight::MAX, Height::MAX);
+        assert_eq!(Height::from(NonZeroHeight::MAX), Height::MAX);
+
+        let too_tall = Height::MAX.get() + 1;
+        assert_eq!(Height::new(too_tall), None);
+        assert_eq!(NonZeroHeight::new(NonZeroU8::new(too_tall).unwrap()), None);
+        assert_eq!(Height::MAX.try_increment(), None);
+    }
+
+    #[test]
+    fn height_ord() {
+        let heights: Vec<Height> = (0 ..= Height::MAX.get()).map(|n| Height::new(n).unwrap()).collect();
+        assert!(heights.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(heights.iter().min(), Some(&Height::MIN));
+        assert_eq!(heights.iter().max(), Some(&Height::MAX));
+
+        // Ordering is preserved by converting a NonZeroHeight into a Height
+        for a in &heights[1 ..] {
+            for b in &heights {
+                let a_nz = NonZeroHeight::try_from(*a).unwrap();
+                let a_h: Height = a_nz.into();
+                assert_eq!(a_h.cmp(b), a.cmp(b));
+                if let Ok(b_nz) = NonZeroHeight::try_from(*b) {
+                    assert_eq!(a_nz.cmp(&b_nz), a.cmp(b));
+                }
+                assert_eq!(a_nz.partial_cmp(b), a.partial_cmp(b));
+                assert_eq!(b.partial_cmp(&a_nz), b.partial_cmp(a));
+            }
+        }
+        assert!(Height::ZERO < NonZeroHeight::MIN);
+    }
+
+    #[test]
+    fn height_from_u8() {
+        let max = Height::MAX.get();
+        assert_eq!(Height::from_u8(0), Some(Height::ZERO));
+        assert_eq!(Height::from_u8(max), Some(Height::MAX));
+        assert_eq!(Height::from_u8(max + 1), None);
+        assert_eq!(Height::from_u8(u8::MAX), None);
+
+        assert_eq!(NonZeroHeight::from_u8(0), None);
+        assert_eq!(NonZeroHeight::from_u8(1), Some(NonZeroHeight::MIN));
+        assert_eq!(NonZeroHeight::from_u8(max), Some(NonZeroHeight::MAX));
+        assert_eq!(NonZeroHeight::from_u8(max + 1), None);
+        assert_eq!(NonZeroHeight::from_u8(u8::MAX), None);
+    }
+
+    #[test]
+    fn height_try_from_len() {
+        assert_eq!(Height::try_from_len(1), Some(Height::ZERO));
+        assert_eq!(Height::try_from_len(8), Height::new(3));
+        assert_eq!(Height::try_from_len(1 << Height::MAX.get()), Some(Height::MAX));
+        assert_eq!(Height::try_from_len(6), None);
+        assert_eq!(Height::try_from_len(0), None);
+
+        for n in 0 ..= Height::MAX.get() {
+            let height = Height::new(n).unwrap();
+            assert_eq!(Height::try_from_len(height.len()), Some(height));
+        }
+    }
+}
@@ -14,7 +14,7 @@ use hoard::primitive::Primitive;
 use hoard::blob::{Blob, BlobDyn, Bytes, BytesUninit};
 use hoard::load::{MaybeValid, Load, LoadRef};
 use hoard::save::{Save, SavePoll, Saver};
-use hoard::ptr::{AsZone, Zone, Get, GetMut, Ptr, PtrClean, PtrBlob};
+use hoard::ptr::{AsZone, Alloc, Zone, Get, GetMut, Ptr, PtrClean, PtrBlob};
 use hoard::pointee::Pointee;
 use hoard::owned::{IntoOwned, Take, RefOwn, Ref};
 use hoard::bag::Bag;
@@ -29,6 +29,9 @@ use crate::commit::{
 use super::raw;
 
 /// Leaf node in a tree.
+///
+/// The value is stored behind its own `P` pointer, so the leaf blob is the same size regardless of
+/// `T`, and the value is only loaded when needed.
 #[repr(transparent)]
 pub struct Leaf<T, P: Ptr = (), D: Digest = Sha256Digest> {
     raw: ManuallyDrop<raw::Node<T, P, D>>,
@@ -58,12 +61,45 @@ impl<T, P: Ptr, D: Digest> Leaf<T, P, D> {
     {
         Self::new_unchecked(None, P::alloc(value))
     }
+
+    /// Creates a new leaf, allocating the value in `zone`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `zone` can't allocate; see `try_new_in`.
+    #[track_caller]
+    pub fn new_in<Z>(value: T, zone: &mut Z) -> Self
+        where Z: Alloc<Ptr = P>
+    {
+        Self::try_new_in(value, zone).expect("allocation failed")
+    }
+
+    /// Creates a new leaf, allocating the value in `zone`, or returning the error if `zone` can't
+    /// allocate.
+    pub fn try_new_in<Z>(value: T, zone: &mut Z) -> Result<Self, Z::Error>
+        where Z: Alloc<Ptr = P>
+    {
+        Ok(Self::new_unchecked(None, zone.try_alloc(value)?))
+    }
+
+    /// Creates a new leaf from an already allocated value, without reallocating it.
+    ///
+    /// The digest is calculated on demand, so `bag` must be dirty.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if `bag` is clean: its digest couldn't be calculated without
+    /// loading it. Use `new_unchecked` with the digest instead.
+    #[track_caller]
+    pub fn from_bag(bag: Bag<T, P>) -> Self {
+        Self::new_unchecked(None, bag)
+    }
 }
 
 impl<T, P: Ptr, D: Digest> Leaf<T, P, D> {
     pub fn new_unchecked(digest: Option<D>, bag: Bag<T, P>) -> Self {
         let (ptr, ()) = bag.into_raw_parts();
-        let raw = raw::Node::new(digest, ptr);
+        let raw = raw::Node::new_checked(digest, ptr);
 
         unsafe {
             Self::from_raw(raw)
@@ -92,7 +128,7 @@ impl<T, P: Ptr, D: Digest> Leaf<T, P, D> {
     }
 
     /// Returns a hash commit to the `T` value, re-hashing if necessary.
-    fn value_commit(&self) -> HashCommit<T::Commitment, D>
+    pub(crate) fn value_commit(&self) -> HashCommit<T::Commitment, D>
         where T: Commit
     {
         self.try_value_commit()
@@ -110,11 +146,26 @@ impl<T, P: Ptr, D: Digest> Leaf<T, P, D> {
     }
 
     /// Returns a hash commit to the `T` value, if available.
-    fn try_value_commit(&self) -> Option<HashCommit<T::Commitment, D>>
+    pub(crate) fn try_value_commit(&self) -> Option<HashCommit<T::Commitment, D>>
         where T: Commit
     {
         self.raw.digest().map(HashCommit::from_digest)
     }
+
+    /// Returns the digest of the leaf value, re-hashing if necessary.
+    ///
+    /// The digest is cached in the leaf, and is the same digest a tree uses for this leaf. This
+    /// makes it possible to pre-hash values before inserting them into a tree.
+    pub fn digest(&self) -> D
+        where T: Commit
+    {
+        self.value_commit().digest()
+    }
+
+    /// Returns the digest of the leaf value, if already available.
+    pub fn try_digest(&self) -> Option<D> {
+        self.raw.digest()
+    }
 }
 
 impl<T, P: Ptr, D: Digest> Leaf<T, P, D>
@@ -130,6 +181,21 @@ where T: Load,
         }
     }
 
+    /// Returns a copy of the leaf value.
+    ///
+    /// ```
+    /// # use hoard::ptr::Heap;
+    /// # use proofmarshal_core::collections::leaf::Leaf;
+    /// let leaf = Leaf::<u8, Heap>::new(42);
+    /// assert_eq!(leaf.copied(), 42);
+    /// ```
+    pub fn copied(&self) -> T
+        where P: Get,
+              T: Copy,
+    {
+        *self.get()
+    }
+
     pub fn get_mut(&mut self) -> &mut T
         where P: GetMut
     {
@@ -148,6 +214,15 @@ where T: Load,
                .trust()
         }
     }
+
+    /// Calculates the leaf digest with a custom digest function.
+    ///
+    /// The result is not cached, and `Commit` always uses the default `T::Commitment` digest.
+    pub fn commit_with(&self, f: impl Fn(&T) -> D) -> D
+        where P: Get
+    {
+        f(&*self.get())
+    }
 }
 
 impl<T, P: Ptr, D: Digest> Leaf<T, P, D> {
@@ -305,6 +380,14 @@ where P::Zone: AsZone<T::Zone>,
         let raw = self.encode_raw_node_blob();
         unsafe { Leaf::from_raw(raw) }
     }
+
+    fn blobs_remaining(&self) -> usize {
+        match &self.state {
+            State::Clean(_) => 1,
+            State::Dirty(target_poll) => 1 + target_poll.blobs_remaining(),
+            State::Done(_) => 0,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -315,12 +398,23 @@ mod tests {
         ptr::{
             Heap,
             key::{
-                Map,
+                Key, Map, Offset, TracingZone,
                 offset::OffsetSaver,
             },
         },
     };
 
+    use crate::collections::perfecttree::PerfectTree;
+
+    #[test]
+    fn from_bag() {
+        let bag = Heap::alloc(42u8);
+        let leaf = Leaf::<u8, Heap>::from_bag(bag);
+        assert_eq!(*leaf.get(), 42);
+        assert!(leaf.try_digest().is_none());
+        assert_eq!(leaf.digest(), Leaf::<u8, Heap>::new(42).digest());
+    }
+
     #[test]
     fn save() {
         let n = 42u8;
@@ -336,6 +430,54 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn large_value_out_of_line() {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        struct Large([u8; 1000]);
+
+        impl Primitive for Large {
+            const BLOB_SIZE: usize = 1000;
+            type DecodeBytesError = !;
+
+            fn encode_blob_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
+                dst.write_bytes(&self.0)
+            }
+
+            fn decode_blob_bytes(blob: Bytes<'_, Self>) -> Result<Self, !> {
+                Ok(Self(TryFrom::try_from(&blob[..]).unwrap()))
+            }
+        }
+
+        crate::impl_commit!(Large);
+
+        let mut value = Large([0u8; 1000]);
+        value.0[999] = 42;
+        let leaf = Leaf::<Large, Heap>::new(value);
+
+        // The leaf blob is a digest and a pointer, regardless of the size of the value
+        assert_eq!(<Leaf<Large, Offset> as Blob>::SIZE, 32 + 8);
+
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&leaf).unwrap();
+        assert_eq!(offset, 1000);
+        assert_eq!(buf.len(), 1000 + 40);
+
+        let zone = TracingZone::new(&buf[..]);
+        let zone_ref = &zone;
+        let key = Key::<TracingZone<&[u8]>>::from_blob(offset, &zone_ref);
+        let bag: Bag<Leaf<Large, Key<TracingZone<&[u8]>>>, _> = unsafe { Bag::from_raw_parts(key, ()) };
+
+        // Loading the leaf doesn't load the value...
+        let loaded = bag.get();
+        assert_eq!(zone.take_trace(), vec![offset]);
+        assert_eq!(loaded.try_digest(), Some(HashCommit::<Large>::new(&value).digest()));
+        assert!(zone.take_trace().is_empty());
+
+        // ...until it's needed
+        assert_eq!(loaded.get().0[999], 42);
+        assert_eq!(zone.take_trace(), vec![Offset::new(0)]);
+    }
+
     #[test]
     fn value_commit() {
         let n = 42u8;
@@ -357,6 +499,35 @@ mod tests {
         assert_eq!(leaf_n.value_commit(), HashCommit::new(&43u8));
     }
 
+    #[test]
+    fn digest() {
+        let leaf = Leaf::<u8, Heap>::new(42);
+        assert_eq!(leaf.try_digest(), None);
+
+        let digest = leaf.digest();
+        assert_eq!(digest, HashCommit::<u8>::new(&42u8).digest());
+        assert_eq!(leaf.try_digest(), Some(digest));
+        assert_eq!(leaf.digest(), digest);
+
+        let tree = PerfectTree::from(leaf);
+        assert_eq!(tree.node_digest(), digest);
+    }
+
+    #[test]
+    fn commit_with() {
+        let leaf = Leaf::<u8, Heap>::new(42);
+
+        let default = leaf.commit_with(|n| HashCommit::<u8>::new(n).digest());
+        assert_eq!(default, leaf.value_commit().digest());
+
+        let canonical = leaf.commit_with(|n| HashCommit::<u8>::new(&(n & 0xf0)).digest());
+        assert_eq!(canonical, HashCommit::<u8>::new(&0x20u8).digest());
+        assert_ne!(canonical, default);
+
+        // The cached digest is unaffected
+        assert_eq!(leaf.try_value_commit().unwrap().digest(), default);
+    }
+
     #[test]
     fn to_commitment() {
         let n = 42u8;
@@ -15,6 +15,10 @@ use hoard::primitive::Primitive;
 use crate::{unreachable_unchecked, impl_commit};
 use crate::collections::height::{Height, NonZeroHeight};
 
+// Lengths of perfect trees are computed with unchecked shifts, so every height must be less than
+// `usize::BITS`.
+static_assertions::const_assert!((Height::MAX.get() as u32) < usize::BITS);
+
 pub trait ToLength {
     fn to_length(&self) -> Length;
 }
@@ -68,6 +72,9 @@ impl Length {
     pub const MIN: Self = Length(0);
     pub const ZERO: Self = Length(0);
 
+    /// Returns the length of a perfect tree of `height`.
+    ///
+    /// Never overflows, as `Height::MAX` is less than the number of bits in a `usize`.
     pub fn from_height(height: impl Into<Height>) -> Self {
         let height = height.into();
         Self(1 << height.get())
@@ -140,6 +147,29 @@ impl ToLength for Length {
     }
 }
 
+/// The result of adding to a length with `InnerLength::checked_add` or `push_peak`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddResult<L = Length> {
+    /// The sum, which did not carry into a single perfect tree.
+    Fits(L),
+
+    /// The addition carried, leaving a single perfect tree of this height.
+    CarriesToHeight(Height),
+
+    /// The addition overflowed.
+    Overflow,
+}
+
+impl AddResult<InnerLength> {
+    fn from_sum(sum: Option<NonZeroLength>) -> Self {
+        match sum.map(NonZeroLength::try_into_inner_length) {
+            Some(Ok(sum)) => AddResult::Fits(sum),
+            Some(Err(height)) => AddResult::CarriesToHeight(height),
+            None => AddResult::Overflow,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NonZeroLength(NonZeroUsize);
 
@@ -176,6 +206,9 @@ impl NonZeroLength {
         Self(NonZeroUsize::new_unchecked(len))
     }
 
+    /// Returns the length of a perfect tree of `height`.
+    ///
+    /// Never overflows, as `Height::MAX` is less than the number of bits in a `usize`.
     pub fn from_height(height: impl Into<Height>) -> Self {
         let height = height.into();
         Self::new(1 << height.get())
@@ -204,30 +237,12 @@ impl NonZeroLength {
     }
 
     #[track_caller]
-    pub fn push_peak(self, right: impl Into<Height>) -> Result<InnerLength, Option<NonZeroHeight>> {
+    pub fn push_peak(self, right: impl Into<Height>) -> AddResult<InnerLength> {
         let right = right.into();
-        if self.min_height() > right {
-            let right = NonZeroLength::from_height(right);
-            let sum = self.0 | right.0;
-            Ok(InnerLength(sum))
-        } else if self.min_height() == right {
-            let right_len = NonZeroLength::from_height(right);
-            match self.get().get().checked_add(right_len.into()) {
-                None => Err(None),
-                Some(sum) if sum.is_power_of_two() => {
-                    let height = u8::try_from(sum.trailing_zeros()).unwrap();
-
-                    let height = unsafe { NonZeroU8::new_unchecked(height) };
-                    let height = unsafe { NonZeroHeight::new_unchecked(height) };
-
-                    Err(Some(height))
-                },
-                Some(sum) => {
-                    unsafe {
-                        Ok(InnerLength::new_unchecked(sum))
-                    }
-                }
-            }
+        if self.min_height() >= right {
+            // As the peak is no taller than our smallest peak, the sum is either at least two
+            // peaks, or carries all the way into a single peak
+            AddResult::from_sum(self.checked_add(Length::from_height(right)))
         } else {
             panic!("can't push: self.min_height() = {} < {}", self.min_height(), right)
         }
@@ -235,7 +250,10 @@ impl NonZeroLength {
 
     /// Tries to converts a `NonZeroLength` into an `InnerLength`.
     ///
-    /// If this is *not* possible, returns the `Height` of the remaining tree.
+    /// A length is an inner length if it has at least two bits set, ie. it isn't a power of two.
+    /// An MMR of that length has more than one peak, so its root is an inner node that can be
+    /// split into left and right. Otherwise the length is a single perfect tree, a lone peak, and
+    /// its `Height` — the position of the only set bit — is returned instead.
     ///
     /// ```
     /// # use proofmarshal_core::collections::length::{NonZeroLength, InnerLength};
@@ -357,22 +375,22 @@ impl InnerLength {
     /// # Examples
     ///
     /// ```
-    /// # use proofmarshal_core::collections::length::{InnerLength, NonZeroLength};
+    /// # use std::convert::TryFrom;
+    /// # use proofmarshal_core::collections::length::{InnerLength, AddResult};
+    /// # use proofmarshal_core::collections::height::Height;
     /// assert_eq!(InnerLength::new(0b11).unwrap()
     ///                        .checked_add(0b100),
-    ///            Ok(InnerLength::new(0b111).unwrap()));
+    ///            AddResult::Fits(InnerLength::new(0b111).unwrap()));
     ///
     /// assert_eq!(InnerLength::new(0b11).unwrap()
     ///                        .checked_add(0b1),
-    ///            Err(Some(NonZeroLength::new(0b100).unwrap())));
+    ///            AddResult::CarriesToHeight(Height::try_from(2u8).unwrap()));
+    ///
+    /// assert_eq!(InnerLength::MAX.checked_add(1),
+    ///            AddResult::Overflow);
     /// ```
-    pub fn checked_add(self, other: impl Into<Length>) -> Result<Self, Option<NonZeroLength>> {
-        if let Some(len) = self.get().checked_add(other.into().get()) {
-            Self::new(len)
-                 .ok_or(NonZeroLength::new(len))
-        } else {
-            Err(None)
-        }
+    pub fn checked_add(self, other: impl Into<Length>) -> AddResult<Self> {
+        AddResult::from_sum(NonZeroLength::from(self).checked_add(other))
     }
 
     /// Checked push.
@@ -381,22 +399,22 @@ impl InnerLength {
     ///
     /// ```
     /// # use std::convert::TryFrom;
-    /// # use proofmarshal_core::collections::length::InnerLength;
+    /// # use proofmarshal_core::collections::length::{InnerLength, AddResult};
     /// # use proofmarshal_core::collections::height::{Height, NonZeroHeight};
     /// let left = InnerLength::new(0b110).unwrap();
     /// let right = Height::new(0).unwrap();
-    /// assert_eq!(left.push_peak(right).unwrap(),
-    ///            0b111);
+    /// assert_eq!(left.push_peak(right),
+    ///            AddResult::Fits(InnerLength::new(0b111).unwrap()));
     ///
     /// let left = InnerLength::new(0b11).unwrap();
     /// let right = Height::new(0).unwrap();
-    /// assert_eq!(left.push_peak(right).unwrap_err(),
-    ///            Some(NonZeroHeight::try_from(2).unwrap()));
+    /// assert_eq!(left.push_peak(right),
+    ///            AddResult::CarriesToHeight(Height::try_from(2u8).unwrap()));
     ///
     /// let left = InnerLength::MAX;
     /// let right = Height::new(0).unwrap();
-    /// assert_eq!(left.push_peak(right).unwrap_err(),
-    ///            None);
+    /// assert_eq!(left.push_peak(right),
+    ///            AddResult::Overflow);
     /// ```
     ///
     /// ```should_panic
@@ -408,7 +426,7 @@ impl InnerLength {
     /// left.push_peak(right); // panics!
     /// ```
     #[track_caller]
-    pub fn push_peak(self, right: impl Into<Height>) -> Result<Self, Option<NonZeroHeight>> {
+    pub fn push_peak(self, right: impl Into<Height>) -> AddResult<Self> {
         let left = NonZeroLength::from(self);
         left.push_peak(right)
     }
@@ -892,6 +910,82 @@ mod test {
         assert_eq!(right, 1);
     }
 
+    #[test]
+    fn checked_add() {
+        assert_eq!(Length(0).checked_add(0b1),
+                   Some(Length(0b1)));
+        assert_eq!(Length(0b11).checked_add(0b1),
+                   Some(Length(0b100)));
+        assert_eq!(Length(0b11).checked_add(0b101),
+                   Some(Length(0b1000)));
+
+        let max = Height::MAX.get();
+        assert_eq!(Length::MAX.checked_add(1),
+                   None);
+        assert_eq!(Length(1 << max).checked_add(1 << max),
+                   None);
+    }
+
+    #[test]
+    fn inner_checked_add() {
+        let h = |n| Height::new(n).unwrap();
+        let inner = |n| InnerLength::new(n).unwrap();
+
+        assert_eq!(inner(0b11).checked_add(0),
+                   AddResult::Fits(inner(0b11)));
+        assert_eq!(inner(0b11).checked_add(0b100),
+                   AddResult::Fits(inner(0b111)));
+        assert_eq!(inner(0b101).checked_add(0b1),
+                   AddResult::Fits(inner(0b110)));
+
+        assert_eq!(inner(0b11).checked_add(0b1),
+                   AddResult::CarriesToHeight(h(2)));
+        assert_eq!(inner(0b11).checked_add(0b101),
+                   AddResult::CarriesToHeight(h(3)));
+
+        assert_eq!(InnerLength::MAX.checked_add(1),
+                   AddResult::Overflow);
+        assert_eq!(inner(usize::MAX - 1).checked_add(0b10),
+                   AddResult::Overflow);
+    }
+
+    #[test]
+    fn push_peak() {
+        let h = |n| Height::new(n).unwrap();
+        let inner = |n| InnerLength::new(n).unwrap();
+
+        assert_eq!(NonZeroLength::new(0b100).unwrap().push_peak(h(0)),
+                   AddResult::Fits(inner(0b101)));
+        assert_eq!(inner(0b110).push_peak(h(0)),
+                   AddResult::Fits(inner(0b111)));
+
+        assert_eq!(NonZeroLength::new(0b1).unwrap().push_peak(h(0)),
+                   AddResult::CarriesToHeight(h(1)));
+        assert_eq!(NonZeroLength::new(0b100).unwrap().push_peak(h(2)),
+                   AddResult::CarriesToHeight(h(3)));
+        assert_eq!(inner(0b1110).push_peak(h(1)),
+                   AddResult::CarriesToHeight(h(4)));
+        assert_eq!(inner(0b1111).push_peak(h(0)),
+                   AddResult::CarriesToHeight(h(4)));
+
+        let below_max = Height::MAX.get() - 1;
+        assert_eq!(NonZeroLength::from_height(h(below_max)).push_peak(h(below_max)),
+                   AddResult::CarriesToHeight(Height::MAX));
+
+        // Carries that stop short of the top peak leave an inner length
+        assert_eq!(inner(0b1011).push_peak(h(0)),
+                   AddResult::Fits(inner(0b1100)));
+        assert_eq!(inner(0b10110).push_peak(h(1)),
+                   AddResult::Fits(inner(0b11000)));
+        assert_eq!(inner(0b101100).push_peak(h(2)),
+                   AddResult::Fits(inner(0b110000)));
+
+        assert_eq!(InnerLength::MAX.push_peak(h(0)),
+                   AddResult::Overflow);
+        assert_eq!(NonZeroLength::from_height(Height::MAX).push_peak(Height::MAX),
+                   AddResult::Overflow);
+    }
+
     #[test]
     fn ops_bitand() {
         assert_eq!(Length(0) & Length(0),
@@ -903,4 +997,48 @@ mod test {
         assert_eq!(InnerLength::MAX & NonZeroLength::MAX,
                    Length::MAX);
     }
+
+    #[test]
+    fn from_height_max() {
+        let len = NonZeroLength::from_height(Height::MAX);
+        assert_eq!(len.get().get(), 1 << (usize::BITS - 1));
+        assert_eq!(Length::from_height(Height::MAX), Length(usize::MAX / 2 + 1));
+
+        // The next height up would overflow, and isn't a valid height
+        assert_eq!(Height::new(Height::MAX.get() + 1), None);
+        assert!(Height::try_from(usize::BITS as u8).is_err());
+    }
+
+    #[test]
+    fn try_into_inner_length() {
+        // Powers of two are single peaks
+        for i in 0 .. usize::BITS {
+            let len = NonZeroLength::new(1 << i).unwrap();
+            assert_eq!(len.try_into_inner_length(), Err(Height::new(i as u8).unwrap()));
+        }
+
+        // Everything else is an inner node
+        for &n in [0b11, 0b101, 0b110, 0b111, 1000, usize::MAX >> 1, usize::MAX - 1, usize::MAX].iter() {
+            let len = NonZeroLength::new(n).unwrap();
+            assert_eq!(len.try_into_inner_length(), Ok(InnerLength::new(n).unwrap()));
+        }
+
+        // Round trips, exhaustively for small lengths
+        for n in 1 ..= 1024 {
+            let len = NonZeroLength::new(n).unwrap();
+            match len.try_into_inner_length() {
+                Ok(inner) => {
+                    assert!(!n.is_power_of_two());
+                    assert_eq!(inner.get(), n);
+
+                    let (left, right) = inner.split();
+                    assert_eq!(left.get().get() + right.get().get(), n);
+                },
+                Err(height) => {
+                    assert!(n.is_power_of_two());
+                    assert_eq!(1 << height.get(), n);
+                },
+            }
+        }
+    }
 }
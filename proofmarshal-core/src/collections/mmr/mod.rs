@@ -2,6 +2,7 @@
 
 use std::borrow::{Borrow, BorrowMut};
 use std::cmp;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::error;
 use std::marker::PhantomData;
@@ -21,13 +22,14 @@ use hoard::load::{Load, LoadRef, MaybeValid};
 use hoard::save::{Save, SavePoll, Saver};
 
 use crate::commit::{
-    Commit, Digest,
+    Commit, Digest, HashCommit,
     sha256::Sha256Digest,
 };
 use crate::collections::leaf::Leaf;
 use crate::collections::length::*;
-use crate::collections::height::Height;
-use crate::collections::perfecttree::PerfectTree;
+use crate::collections::height::{Height, NonZeroHeight};
+use crate::collections::perfecttree::{self, PerfectTree, PerfectTreeDyn};
+use crate::collections::raw;
 
 pub mod peaktree;
 use self::peaktree::{PeakTree, PeakTreeDyn, DecodePeakTreeBytesError, DecodePeakTreeDynBytesError, PeakTreeSavePoll};
@@ -113,6 +115,13 @@ where T: Load,
         }
     }
 
+    /// Returns an iterator over the peaks, tallest first.
+    pub fn iter_peaks(&self) -> impl Iterator<Item = Ref<PerfectTreeDyn<T, P, D>>>
+        where P: Get
+    {
+        self.peaks.iter().flat_map(|peaks| peaks.peaks())
+    }
+
     pub fn get(&self, idx: usize) -> Option<Ref<T>>
         where P: Get,
     {
@@ -167,6 +176,221 @@ where T: Load,
     }
 }
 
+impl<T, P: Ptr, D: Digest> MMR<T, P, D>
+where T: Commit + Load,
+      P: Get,
+      P::Zone: AsZone<T::Zone>,
+{
+    /// Returns the root digest, bagging every peak together, or `None` if the MMR is empty.
+    ///
+    /// The root commits to the length of the MMR as well as to its items.
+    pub fn commit(&self) -> Option<D> {
+        self.peaks.as_ref().map(|peaks| {
+            HashCommit::<PeakTree<T::Commitment, (), D>, D>::new(&**peaks).digest()
+        })
+    }
+
+    /// Proves that the item at `idx` is included in the MMR.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn prove(&self, idx: usize) -> Option<InclusionProof<D>> {
+        let peaks = self.peaks.as_ref()?;
+        let (height, idx_in_peak) = idx_to_containing_height(peaks.len(), idx)?;
+
+        let mut peak_path = vec![];
+        let tree_path = peaks.prove_in(height, idx_in_peak, &mut peak_path)?;
+        Some(InclusionProof {
+            len: peaks.len(),
+            idx,
+            tree_path,
+            peak_path,
+        })
+    }
+}
+
+/// Proof that an item is included in an `MMR`.
+///
+/// Consists of the digests of the item's siblings within the peak containing it, followed by the
+/// digests bagging that peak together with the other peaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof<D: Digest = Sha256Digest> {
+    len: NonZeroLength,
+    idx: usize,
+    tree_path: Vec<D>,
+    peak_path: Vec<D>,
+}
+
+impl<D: Digest> InclusionProof<D> {
+    /// Returns the length of the MMR this proof is for.
+    pub fn len(&self) -> NonZeroLength {
+        self.len
+    }
+
+    /// Returns the index of the proven item.
+    pub fn idx(&self) -> usize {
+        self.idx
+    }
+
+    /// Verifies that `item` is at this proof's index in the MMR with root digest `root`.
+    pub fn verify<T: Commit>(&self, item: &T, root: &D) -> bool {
+        self.calc_root(item).map_or(false, |calc| calc == *root)
+    }
+
+    /// Verifies a batch of proofs against the same root, stopping at the first failure.
+    ///
+    /// Every proof must be for an MMR of the same length. Where the path of a proof meets the path
+    /// of one already verified, the pair digests above that point are reused rather than hashed
+    /// again: after the first of two adjacent leaves, the second only costs its own leaf hash.
+    pub fn verify_batch<T: Commit>(proofs: &[(T, InclusionProof<D>)], root: &D) -> bool {
+        let len = match proofs.first() {
+            Some((_, proof)) => proof.len,
+            None => return true,
+        };
+
+        // Digests of the nodes, and their siblings, on the paths verified so far
+        let mut known = HashMap::new();
+        proofs.iter().all(|(item, proof)| {
+            let mut path = vec![];
+            let verified = proof.len == len
+                && proof.calc_root_with(item, &known, |pos, digest| path.push((pos, digest)))
+                        .map_or(false, |calc| calc == *root);
+            if verified {
+                known.extend(path);
+            }
+            verified
+        })
+    }
+
+    /// Calculates the root digest of the MMR, assuming `item` is the proven item.
+    ///
+    /// Returns `None` if the proof is malformed.
+    pub fn calc_root<T: Commit>(&self, item: &T) -> Option<D> {
+        self.calc_root_with(item, &HashMap::new(), |_, _| ())
+    }
+
+    /// Calculates the root digest, reusing the `known` digest of any pair whose sides are both
+    /// known, and calling `visit` with every digest on the path along with its siblings.
+    fn calc_root_with<T: Commit>(&self, item: &T, known: &HashMap<Position, D>, mut visit: impl FnMut(Position, D))
+        -> Option<D>
+    {
+        let (height, idx_in_peak) = idx_to_containing_height(self.len, self.idx)?;
+        if self.tree_path.len() != usize::from(height.get()) {
+            return None;
+        }
+
+        let mut digest = HashCommit::<T::Commitment, D>::new(item).digest();
+        let mut pos = Position::in_peak(height, 0, idx_in_peak);
+        visit(pos, digest);
+
+        for (level, sibling) in self.tree_path.iter().enumerate() {
+            let idx = idx_in_peak >> level;
+            let sibling_pos = Position::in_peak(height, level as u8, idx ^ 1);
+            let parent_pos = Position::in_peak(height, level as u8 + 1, idx >> 1);
+
+            digest = known_parent(known, (pos, digest), (sibling_pos, *sibling), parent_pos).unwrap_or_else(|| {
+                let pair_height = NonZeroHeight::from_u8(level as u8 + 1).expect("valid height");
+                let (left, right) = if idx & 1 == 0 {
+                    (digest, *sibling)
+                } else {
+                    (*sibling, digest)
+                };
+                let commitment = unsafe {
+                    perfecttree::Pair::<T::Commitment, (), D>::from_raw_pair(raw_pair(left, right), pair_height)
+                };
+                HashCommit::from_blob_in::<()>(&commitment).digest()
+            });
+
+            visit(sibling_pos, *sibling);
+            visit(parent_pos, digest);
+            pos = parent_pos;
+        }
+
+        // Peak trees are always split the same way for a given length, so descending from the
+        // root finds the side of each pair the peak is on.
+        let mut sides = vec![];
+        let mut len = self.len;
+        while let Ok(inner_len) = len.try_into_inner_length() {
+            let (left, right) = inner_len.split();
+            let is_left = left.contains(height);
+            let (this, other) = if is_left { (left, right) } else { (right, left) };
+            sides.push((inner_len, is_left, other));
+            len = this;
+        }
+
+        if sides.len() != self.peak_path.len() {
+            return None;
+        }
+
+        for ((inner_len, is_left, other_len), other) in sides.into_iter().rev().zip(&self.peak_path) {
+            let other_pos = Position::Peaks(other_len);
+            let parent_pos = Position::Peaks(inner_len.into());
+
+            digest = known_parent(known, (pos, digest), (other_pos, *other), parent_pos).unwrap_or_else(|| {
+                let (left, right) = if is_left {
+                    (digest, *other)
+                } else {
+                    (*other, digest)
+                };
+                let commitment = unsafe {
+                    peaktree::Pair::<T::Commitment, (), D>::from_raw_pair(raw_pair(left, right), inner_len)
+                };
+                HashCommit::from_blob_in::<()>(&commitment).digest()
+            });
+
+            visit(other_pos, *other);
+            visit(parent_pos, digest);
+            pos = parent_pos;
+        }
+
+        let peaks = unsafe {
+            PeakTree::<T::Commitment, (), D>::from_raw_node(raw::Node::new(Some(digest), ()), self.len)
+        };
+        Some(HashCommit::from_blob_in::<()>(&peaks).digest())
+    }
+}
+
+/// Position of a node in an MMR of a given length, used to share digests between proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Position {
+    /// A node within the peak of `height`, `level` levels above the leaves.
+    InPeak {
+        height: Height,
+        level: u8,
+        idx: usize,
+    },
+
+    /// A peak, or a pair of peaks, identified by the length it covers.
+    ///
+    /// Every pair in a peak tree covers a different combination of peaks, so the length is unique.
+    Peaks(NonZeroLength),
+}
+
+impl Position {
+    fn in_peak(height: Height, level: u8, idx: usize) -> Self {
+        if level == height.get() {
+            Position::Peaks(NonZeroLength::from_height(height))
+        } else {
+            Position::InPeak { height, level, idx }
+        }
+    }
+}
+
+/// Returns the known digest of `parent`, if both of its sides are known to have these digests.
+fn known_parent<D: Digest>(known: &HashMap<Position, D>, node: (Position, D), sibling: (Position, D), parent: Position)
+    -> Option<D>
+{
+    if known.get(&node.0) == Some(&node.1) && known.get(&sibling.0) == Some(&sibling.1) {
+        known.get(&parent).copied()
+    } else {
+        None
+    }
+}
+
+fn raw_pair<T, D: Digest>(left: D, right: D) -> raw::Pair<T, (), D> {
+    raw::Pair::new(raw::Node::new(Some(left), ()),
+                   raw::Node::new(Some(right), ()))
+}
+
 /// Determines the height of the peak containing a given index, as well as the index within that
 /// peak tree.
 ///
@@ -229,7 +453,7 @@ fn idx_to_containing_height(len: NonZeroLength, idx: usize) -> Option<(Height, u
         let height = height as u8;
 
         // SAFETY: We've proven that height must be in the valid range for a Height
-        assert_eq!(Height::MAX as u32, usize::MAX.count_ones() - 1);
+        assert_eq!(Height::MAX.get() as u32, usize::MAX.count_ones() - 1);
         debug_assert!(Height::try_from(height).is_ok());
         let height = unsafe { Height::new_unchecked(height) };
 
@@ -280,20 +504,26 @@ where T: 'static,
     }
 
     fn decode_bytes(src: Bytes<'_, Self>) -> Result<MaybeValid<Self>, Self::DecodeBytesError> {
-        let mut fields = src.struct_fields();
+        // An empty MMR has no peaks, so everything prior to the length must be zeroed.
+        let padding_len = <PeakTree<T, P, D> as Blob>::SIZE - <Length as Blob>::SIZE;
+        let zero_padding = src[.. padding_len].iter().all(|b| *b == 0);
 
-        let peaks = match fields.trust_field::<PeakTree<T, P, D>>() {
-            Ok(peaks) => Ok(Some(peaks)),
+        let mut fields = src.struct_fields();
+        match fields.trust_field::<PeakTree<T, P, D>>() {
+            Ok(peaks) => {
+                fields.assert_done();
+                Ok(Self { peaks: Some(peaks) }.into())
+            },
             Err(DecodePeakTreeBytesError::Raw(raw)) => Err(DecodeMMRBytesError::Peaks(DecodePeakTreeDynBytesError(raw))),
             Err(DecodePeakTreeBytesError::NonZeroLength(err)) if err.0 == 0 => {
-                // FIXME: check for non-zero padding
-                Ok(None)
+                if zero_padding {
+                    Ok(Self::new().into())
+                } else {
+                    Err(DecodeMMRBytesError::NonZeroPadding)
+                }
             },
             Err(DecodePeakTreeBytesError::NonZeroLength(_err)) => Err(DecodeMMRBytesError::Len(LengthError)),
-        }?;
-
-        fields.assert_done();
-        Ok(Self { peaks }.into())
+        }
     }
 }
 
@@ -438,6 +668,141 @@ mod tests {
     }
 
 
+    #[test]
+    fn decode_empty() {
+        use hoard::blob::Bytes;
+        use hoard::ptr::key::Offset;
+
+        let decode = |buf: &[u8]| {
+            let bytes = Bytes::<MMR<u8, Offset>>::try_from(buf).unwrap();
+            <MMR<u8, Offset> as Blob>::decode_bytes(bytes).map(|mmr| mmr.trust())
+        };
+
+        let mut buf = vec![0; <MMR<u8, Offset> as Blob>::SIZE];
+        let mmr = decode(&buf).unwrap();
+        assert_eq!(mmr.len(), 0);
+        assert!(mmr.peaks().is_none());
+
+        // Peaks present with a zero length
+        for i in [0, <Sha256Digest as Blob>::SIZE, buf.len() - <Length as Blob>::SIZE - 1].iter().copied() {
+            buf[i] = 1;
+            assert!(matches!(decode(&buf), Err(DecodeMMRBytesError::NonZeroPadding)));
+            buf[i] = 0;
+        }
+    }
+
+    #[test]
+    fn prove() {
+        let mut mmr = MMR::<u8, Heap>::new();
+        assert_eq!(mmr.commit(), None);
+        assert!(mmr.prove(0).is_none());
+
+        for i in 0 .. 3 {
+            mmr.try_push(i).unwrap();
+        }
+        let root = mmr.commit().unwrap();
+
+        let proof = mmr.prove(1).unwrap();
+        assert_eq!(proof.len(), 3);
+        assert!(proof.verify(&1u8, &root));
+        assert!(!proof.verify(&0u8, &root));
+        assert!(!proof.verify(&1u8, &Sha256Digest::default()));
+        assert!(mmr.prove(3).is_none());
+
+        // The root commits to the length
+        mmr.try_push(3).unwrap();
+        assert_ne!(mmr.commit(), Some(root));
+        assert!(!proof.verify(&1u8, &mmr.commit().unwrap()));
+    }
+
+    #[test]
+    fn verify_batch() {
+        let mut mmr = MMR::<u8, Heap>::new();
+        for i in 0 .. 7 {
+            mmr.try_push(i).unwrap();
+        }
+        let root = mmr.commit().unwrap();
+
+        // Adjacent leaves, sharing everything but the leaf level
+        let mut proofs = vec![(2u8, mmr.prove(2).unwrap()), (3u8, mmr.prove(3).unwrap())];
+        assert!(InclusionProof::verify_batch(&proofs, &root));
+        assert!(InclusionProof::<Sha256Digest>::verify_batch::<u8>(&[], &root));
+        assert!(!InclusionProof::verify_batch(&proofs, &Sha256Digest::default()));
+
+        proofs[1].0 = 4;
+        assert!(!InclusionProof::verify_batch(&proofs, &root));
+
+        // Proofs for different lengths can't share a root
+        mmr.try_push(7).unwrap();
+        proofs[1] = (7, mmr.prove(7).unwrap());
+        assert!(!InclusionProof::verify_batch(&proofs, &root));
+        assert!(!InclusionProof::verify_batch(&proofs, &mmr.commit().unwrap()));
+    }
+
+    #[test]
+    fn verify_batch_shared_paths() {
+        let mut mmr = MMR::<u8, Heap>::new();
+        for i in 0 .. 13u8 {
+            mmr.try_push(i).unwrap();
+        }
+        let root = mmr.commit().unwrap();
+
+        let proofs: Vec<_> = (0 .. 13u8).map(|i| (i, mmr.prove(i as usize).unwrap())).collect();
+        assert!(InclusionProof::verify_batch(&proofs, &root));
+
+        // Reusing known digests still checks every sibling, above and below where paths meet
+        for idx in 1 .. proofs.len() {
+            let mut bad = proofs.clone();
+            if let Some(digest) = bad[idx].1.peak_path.last_mut() {
+                *digest = Sha256Digest::default();
+                assert!(!InclusionProof::verify_batch(&bad, &root));
+            }
+
+            let mut bad = proofs.clone();
+            if let Some(digest) = bad[idx].1.tree_path.first_mut() {
+                *digest = Sha256Digest::default();
+                assert!(!InclusionProof::verify_batch(&bad, &root));
+            }
+
+            let mut bad = proofs.clone();
+            bad[idx].0 = 42;
+            assert!(!InclusionProof::verify_batch(&bad, &root));
+        }
+    }
+
+    #[test]
+    fn prove_every_index() {
+        let mut mmr = MMR::<u8, Heap>::new();
+        for i in 0 .. 20 {
+            mmr.try_push(i).unwrap();
+
+            let root = mmr.commit().unwrap();
+            for j in 0 ..= i {
+                let proof = mmr.prove(j as usize).unwrap();
+                assert!(proof.verify(&j, &root));
+                assert!(!proof.verify(&(j + 1), &root));
+            }
+        }
+    }
+
+    #[test]
+    fn iter_peaks() {
+        let mut mmr = MMR::<u8, Heap>::new();
+        assert_eq!(mmr.iter_peaks().count(), 0);
+
+        for i in 0 .. 3 {
+            mmr.try_push(i).unwrap();
+        }
+        let heights: Vec<u8> = mmr.iter_peaks().map(|peak| peak.height().get()).collect();
+        assert_eq!(heights, vec![1, 0]);
+
+        for i in 3 .. 11 {
+            mmr.try_push(i).unwrap();
+        }
+        let heights: Vec<u8> = mmr.iter_peaks().map(|peak| peak.height().get()).collect();
+        assert_eq!(heights, vec![3, 1, 0]);
+    }
+
     #[test]
     fn heap_get() {
         let mut mmr = MMR::<u32,Heap>::new();
@@ -480,17 +845,17 @@ mod tests {
 
         mmr.try_push(43).unwrap();
         t(&mmr, 82, &[
-            42, 43, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 250, 74, 74, 79, 147, 58, 207, 22, 6, 235, 253, 179, 116, 242, 232, 247, 23, 215, 116, 250, 221, 195, 13, 100, 238, 143, 228, 191, 182, 184, 237, 154, 2, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0
+            42, 43, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 144, 166, 31, 71, 11, 60, 188, 148, 181, 232, 180, 157, 94, 143, 94, 219, 159, 97, 255, 207, 94, 51, 109, 15, 214, 181, 46, 53, 44, 173, 99, 39, 2, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0
         ]);
 
         mmr.try_push(44).unwrap();
         t(&mmr, 163, &[
-            42, 43, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 44, 250, 74, 74, 79, 147, 58, 207, 22, 6, 235, 253, 179, 116, 242, 232, 247, 23, 215, 116, 250, 221, 195, 13, 100, 238, 143, 228, 191, 182, 184, 237, 154, 2, 0, 0, 0, 0, 0, 0, 0, 44, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 82, 0, 0, 0, 0, 0, 0, 0, 138, 125, 6, 251, 217, 239, 106, 103, 2, 109, 242, 95, 16, 143, 157, 213, 3, 198, 231, 236, 185, 164, 188, 23, 157, 97, 151, 110, 147, 47, 235, 190, 83, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0
+            42, 43, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 44, 144, 166, 31, 71, 11, 60, 188, 148, 181, 232, 180, 157, 94, 143, 94, 219, 159, 97, 255, 207, 94, 51, 109, 15, 214, 181, 46, 53, 44, 173, 99, 39, 2, 0, 0, 0, 0, 0, 0, 0, 44, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 82, 0, 0, 0, 0, 0, 0, 0, 79, 244, 148, 138, 21, 130, 90, 31, 66, 195, 72, 156, 157, 182, 186, 199, 103, 97, 64, 21, 78, 226, 235, 101, 151, 149, 238, 180, 34, 196, 220, 178, 83, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0
         ]);
 
         mmr.try_push(45).unwrap();
         t(&mmr, 244, &[
-            42, 43, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 44, 45, 44, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 82, 0, 0, 0, 0, 0, 0, 0, 45, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 0, 0, 0, 0, 0, 0, 0, 250, 74, 74, 79, 147, 58, 207, 22, 6, 235, 253, 179, 116, 242, 232, 247, 23, 215, 116, 250, 221, 195, 13, 100, 238, 143, 228, 191, 182, 184, 237, 154, 2, 0, 0, 0, 0, 0, 0, 0, 164, 244, 200, 22, 32, 194, 203, 252, 168, 252, 91, 233, 88, 222, 38, 115, 86, 146, 146, 86, 1, 152, 121, 190, 253, 18, 48, 244, 155, 44, 213, 159, 84, 0, 0, 0, 0, 0, 0, 0, 79, 64, 109, 199, 230, 180, 200, 195, 102, 189, 161, 26, 69, 87, 71, 112, 6, 153, 86, 6, 222, 176, 115, 211, 60, 242, 180, 8, 244, 74, 221, 110, 164, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0
+            42, 43, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 43, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 44, 45, 44, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 82, 0, 0, 0, 0, 0, 0, 0, 45, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 83, 0, 0, 0, 0, 0, 0, 0, 144, 166, 31, 71, 11, 60, 188, 148, 181, 232, 180, 157, 94, 143, 94, 219, 159, 97, 255, 207, 94, 51, 109, 15, 214, 181, 46, 53, 44, 173, 99, 39, 2, 0, 0, 0, 0, 0, 0, 0, 220, 1, 219, 141, 100, 174, 45, 50, 214, 233, 235, 191, 8, 118, 196, 169, 38, 254, 207, 209, 219, 86, 113, 206, 72, 228, 248, 220, 33, 57, 173, 217, 84, 0, 0, 0, 0, 0, 0, 0, 20, 75, 47, 209, 233, 253, 191, 7, 230, 222, 76, 218, 210, 119, 120, 56, 41, 47, 164, 120, 254, 115, 83, 89, 21, 183, 154, 109, 166, 192, 223, 15, 164, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0
         ]);
     }
 
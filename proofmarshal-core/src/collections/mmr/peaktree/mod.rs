@@ -1,5 +1,6 @@
 use std::marker::PhantomData;
 use std::borrow::{Borrow, BorrowMut};
+use std::alloc::Layout;
 use std::fmt;
 use std::error;
 use std::mem::{self, ManuallyDrop};
@@ -14,7 +15,7 @@ use hoard::blob::{Blob, BlobDyn, Bytes, BytesUninit};
 use hoard::load::{MaybeValid, Load, LoadRef};
 use hoard::save::{Save, SavePoll, SaveRef, SaveRefPoll, Saver};
 use hoard::ptr::{AsZone, Zone, Get, GetMut, Ptr, PtrClean, PtrBlob};
-use hoard::pointee::Pointee;
+use hoard::pointee::{MetadataError, Pointee};
 use hoard::owned::{IntoOwned, Take, RefOwn, Ref};
 use hoard::bag::Bag;
 
@@ -160,6 +161,19 @@ where T: Load
             Kind::Inner(inner) => inner.into_get(height),
         }
     }
+
+    fn into_peaks_in(self, peaks: &mut Vec<PerfectTree<T, P, D>>)
+        where P: Get
+    {
+        match self.into_kind() {
+            Kind::Peak(peak) => peaks.push(peak),
+            Kind::Inner(inner) => {
+                let (left, right) = inner.into_pair().into_split();
+                left.into_peaks_in(peaks);
+                right.into_peaks_in(peaks);
+            },
+        }
+    }
 }
 
 impl<T, P: Ptr, D: Digest> PeakTreeDyn<T, P, D>
@@ -174,6 +188,81 @@ where T: Load
             Kind::Inner(inner) => inner.get(height),
         }
     }
+
+    /// Returns an iterator over the peaks, tallest first.
+    pub fn peaks(&self) -> impl Iterator<Item = Ref<PerfectTreeDyn<T, P, D>>>
+        where P: Get
+    {
+        let mut peaks = Vec::new();
+        self.peaks_in(&mut peaks);
+        peaks.into_iter()
+    }
+
+    fn peaks_in<'a>(&'a self, peaks: &mut Vec<Ref<'a, PerfectTreeDyn<T, P, D>>>)
+        where P: Get
+    {
+        match self.kind() {
+            Kind::Peak(peak) => peaks.push(Ref::Borrowed(peak)),
+            Kind::Inner(inner) => {
+                match inner.get_pair() {
+                    Ref::Borrowed(pair) => {
+                        pair.left().peaks_in(peaks);
+                        pair.right().peaks_in(peaks);
+                    },
+                    Ref::Owned(pair) => {
+                        let mut owned = vec![];
+                        let (left, right) = pair.into_split();
+                        left.into_peaks_in(&mut owned);
+                        right.into_peaks_in(&mut owned);
+                        peaks.extend(owned.into_iter().map(Ref::Owned));
+                    },
+                }
+            },
+        }
+    }
+}
+
+impl<T, P: Ptr, D: Digest> PeakTreeDyn<T, P, D>
+where T: Commit + Load,
+      P: Get,
+      P::Zone: AsZone<T::Zone>,
+{
+    /// Returns the digest of the root node, calculating it if necessary.
+    pub fn node_digest(&self) -> D {
+        match self.kind() {
+            Kind::Peak(peak) => peak.node_digest(),
+            Kind::Inner(inner) => inner.pair_commit().digest(),
+        }
+    }
+
+    /// Collects the digests proving the item at `idx` within the peak of `height`.
+    ///
+    /// Returns the digests of the item's siblings within the peak, leaf first. The digests of the
+    /// other side of each pair above the peak are pushed to `peak_path`, peak first.
+    pub(crate) fn prove_in(&self, height: Height, idx: usize, peak_path: &mut Vec<D>) -> Option<Vec<D>> {
+        match self.kind() {
+            Kind::Peak(peak) if peak.height() == height => {
+                (0 .. height.get()).map(|level| {
+                    let level = Height::new(level).expect("valid height");
+                    peak.digest_at(level, (idx >> level.get()) ^ 1)
+                }).collect()
+            },
+            Kind::Peak(_) => None,
+            Kind::Inner(inner) => {
+                let pair = inner.get_pair();
+                let (left, right) = pair.split();
+                let (this, other) = if left.len().contains(height) {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+
+                let tree_path = this.prove_in(height, idx, peak_path)?;
+                peak_path.push(other.node_digest());
+                Some(tree_path)
+            },
+        }
+    }
 }
 
 impl<T, P: Ptr, D: Digest> PeakTreeDyn<T, P, D> {
@@ -247,7 +336,7 @@ where T: Load
         where P: Default + GetMut
     {
         match self.len().push_peak(peak.height()) {
-            Ok(new_len) => {
+            AddResult::Fits(new_len) => {
                 let (new_left_len, _new_right_len) = new_len.split();
                 //eprintln!("new_left_len = 0b{:b}, new_right_len = 0b{:b}", new_left_len, new_right_len);
 
@@ -273,8 +362,8 @@ where T: Load
 
                 }
             },
-            Err(Some(_height)) => Ok(self.merge_peak(peak).into()),
-            Err(None) => {
+            AddResult::CarriesToHeight(_height) => Ok(self.merge_peak(peak).into()),
+            AddResult::Overflow => {
                 Err((self, peak))
             }
         }
@@ -592,21 +681,26 @@ impl<T, P: Ptr, D: Digest> PeakTreeDyn<T, P, D> {
 // ------- pointee impls ----------
 
 macro_rules! impl_pointee {
-    ($t:ident, $meta_ty:ty) => {
+    ($t:ident, $meta_ty:ty, $raw:ident) => {
         impl<T, P: Ptr, D: Digest> Pointee for $t<T, P, D> {
             type Metadata = $meta_ty;
             type LayoutError = !;
 
+            fn try_layout(_: Self::Metadata) -> Result<Layout, !> {
+                Ok(Layout::new::<ManuallyDrop<raw::$raw<T, P, D>>>())
+            }
+
             fn metadata(ptr: *const Self) -> Self::Metadata {
-                unsafe {
-                    let ptr: *const [()] = mem::transmute(ptr);
-                    let len: usize = ptr.len();
+                Self::try_metadata(ptr).unwrap_or_else(|_|
+                    unsafe { unreachable_unchecked!("invalid metadata") }
+                )
+            }
 
-                    <$meta_ty>::try_from(len)
-                               .unwrap_or_else(|_|
-                                   unreachable_unchecked!("invalid metadata")
-                               )
-                }
+            fn try_metadata(ptr: *const Self) -> Result<Self::Metadata, MetadataError> {
+                let ptr: *const [()] = unsafe { mem::transmute(ptr) };
+                let len: usize = ptr.len();
+
+                <$meta_ty>::try_from(len).map_err(|_| MetadataError)
             }
 
             fn make_fat_ptr(thin: *const (), len: Self::Metadata) -> *const Self {
@@ -624,9 +718,9 @@ macro_rules! impl_pointee {
     }
 }
 
-impl_pointee!(PeakTreeDyn, NonZeroLength);
-impl_pointee!(InnerDyn, InnerLength);
-impl_pointee!(PairDyn, InnerLength);
+impl_pointee!(PeakTreeDyn, NonZeroLength, Node);
+impl_pointee!(InnerDyn, InnerLength, Node);
+impl_pointee!(PairDyn, InnerLength, Pair);
 
 // --------- deref impls ----------
 
@@ -665,11 +759,14 @@ macro_rules! impl_deref {
             type Owned = $t<T, P, D>;
 
             fn into_owned(self: RefOwn<'_, Self>) -> Self::Owned {
+                // Nothing may run between the leak and the read: if it panicked, the value
+                // would be neither dropped by the RefOwn, nor owned by the result.
+                let len = self.len();
                 let this = RefOwn::leak(self);
                 unsafe {
                     $t {
                         marker: PhantomData,
-                        len: this.len(),
+                        len,
                         raw: ptr::read(&this.raw),
                     }
                 }
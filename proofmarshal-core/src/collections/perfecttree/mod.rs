@@ -2,12 +2,16 @@
 
 use std::marker::PhantomData;
 use std::borrow::{Borrow, BorrowMut};
+use std::alloc::Layout;
 use std::fmt;
 use std::error;
+use std::cmp;
+use std::collections::HashMap;
 use std::mem::{self, ManuallyDrop};
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 use std::convert::TryFrom;
 use std::ptr;
+use std::hash;
 
 use thiserror::Error;
 
@@ -15,14 +19,15 @@ use hoard::primitive::Primitive;
 use hoard::blob::{Blob, BlobDyn, Bytes, BytesUninit};
 use hoard::load::{MaybeValid, Load, LoadRef};
 use hoard::save::{Save, SavePoll, SaveRef, SaveRefPoll, Saver};
-use hoard::ptr::{AsZone, Zone, Get, GetMut, Ptr, PtrClean, PtrBlob};
-use hoard::pointee::Pointee;
+use hoard::ptr::{AsZone, Alloc, Zone, Get, GetMut, Ptr, PtrClean, PtrBlob};
+use hoard::pointee::{MetadataError, Pointee};
 use hoard::owned::{IntoOwned, Take, RefOwn, Ref};
 use hoard::bag::Bag;
+use hoard::ptr::key::{Key, Offset, PileMut, offset::{self, EMPTY_PILE, JoinAndSaveError, SizeSaver, save_dyn}};
 
 use crate::commit::{
-    Commit, Digest,
-    HashCommit,
+    Commit, CommitContext, Digest,
+    HashCommit, TreeHash,
     sha256::Sha256Digest,
 };
 use crate::unreachable_unchecked;
@@ -32,6 +37,12 @@ use super::length::*;
 use super::raw;
 use super::leaf::{Leaf, LeafSavePoll};
 
+pub mod view;
+pub use self::view::{PerfectTreeView, ViewError};
+
+pub mod stream;
+pub use self::stream::StreamingTreeBuilder;
+
 /// An inner node within a `Tip`.
 #[repr(C)]
 pub struct Pair<T, P: Ptr, D: Digest = Sha256Digest> {
@@ -84,6 +95,9 @@ pub struct PerfectTreeDyn<T, P: Ptr, D: Digest = Sha256Digest> {
     height: HeightDyn,
 }
 
+/// The commitment to a `PerfectTree` of `T` items, returned by `to_commitment`.
+pub type Commitment<T, D = Sha256Digest> = PerfectTree<<T as Commit>::Commitment, (), D>;
+
 /// Returned by `kind` and similar functions that return the type (leaf or tip) of a perfect tree.
 #[derive(Debug)]
 pub enum Kind<Leaf, Tip> {
@@ -94,6 +108,48 @@ pub enum Kind<Leaf, Tip> {
     Tip(Tip),
 }
 
+impl<Leaf, Tip> Kind<Leaf, Tip> {
+    /// Maps the leaf arm, leaving a tip unchanged.
+    ///
+    /// ```
+    /// # use hoard::ptr::Heap;
+    /// # use proofmarshal_core::collections::perfecttree::PerfectTree;
+    /// let tree = PerfectTree::<u8, Heap>::try_join(PerfectTree::new_leaf(1),
+    ///                                              PerfectTree::new_leaf(2)).unwrap();
+    ///
+    /// let describe = |tree: &PerfectTree<u8, Heap>| {
+    ///     tree.kind()
+    ///         .map_leaf(|leaf| format!("leaf {}", *leaf.get()))
+    ///         .map_tip(|tip| format!("tip {}", tip.height().get()))
+    ///         .fold(|s| s, |s| s)
+    /// };
+    /// assert_eq!(describe(&tree), "tip 1");
+    /// assert_eq!(describe(&PerfectTree::new_leaf(42)), "leaf 42");
+    /// ```
+    pub fn map_leaf<U>(self, f: impl FnOnce(Leaf) -> U) -> Kind<U, Tip> {
+        match self {
+            Kind::Leaf(leaf) => Kind::Leaf(f(leaf)),
+            Kind::Tip(tip) => Kind::Tip(tip),
+        }
+    }
+
+    /// Maps the tip arm, leaving a leaf unchanged.
+    pub fn map_tip<U>(self, f: impl FnOnce(Tip) -> U) -> Kind<Leaf, U> {
+        match self {
+            Kind::Leaf(leaf) => Kind::Leaf(leaf),
+            Kind::Tip(tip) => Kind::Tip(f(tip)),
+        }
+    }
+
+    /// Converts either arm to a single value.
+    pub fn fold<R>(self, leaf_f: impl FnOnce(Leaf) -> R, tip_f: impl FnOnce(Tip) -> R) -> R {
+        match self {
+            Kind::Leaf(leaf) => leaf_f(leaf),
+            Kind::Tip(tip) => tip_f(tip),
+        }
+    }
+}
+
 impl<T, P: Ptr, D: Digest> PerfectTree<T, P, D> {
     pub fn try_join(left: PerfectTree<T, P, D>, right: PerfectTree<T, P, D>) -> Result<Self, (PerfectTree<T, P, D>, PerfectTree<T, P, D>)>
         where P: Default
@@ -107,6 +163,89 @@ impl<T, P: Ptr, D: Digest> PerfectTree<T, P, D> {
     {
         Self::from(Leaf::new(value))
     }
+
+    /// Appends `other` to the end of this tree, doubling its size.
+    ///
+    /// Both trees must have the same height; if not, they're returned unchanged.
+    pub fn append(self, other: PerfectTree<T, P, D>) -> Result<Self, (Self, Self)>
+        where P: Default
+    {
+        Self::try_join(self, other)
+    }
+
+    /// Builds a tree from `items`, appending `pad()` values up to the next power of two.
+    ///
+    /// Returns the tree along with the unpadded number of items, or `None` if `items` is empty.
+    pub fn try_from_padded(mut items: Vec<T>, pad: impl Fn() -> T) -> Option<(Self, usize)>
+        where P: Default
+    {
+        let len = items.len();
+        if len == 0 {
+            return None;
+        }
+        items.resize_with(len.checked_next_power_of_two()?, pad);
+
+        let mut trees: Vec<Self> = items.into_iter().map(Self::new_leaf).collect();
+        while trees.len() > 1 {
+            let mut pairs = trees.into_iter();
+            trees = Vec::with_capacity(pairs.len() / 2);
+            while let (Some(left), Some(right)) = (pairs.next(), pairs.next()) {
+                trees.push(Self::try_join(left, right).ok().expect("trees have equal heights"));
+            }
+        }
+        trees.pop().map(|tree| (tree, len))
+    }
+
+    /// Joins two trees, allocating the new pair in `zone`.
+    pub fn try_join_in<Z>(left: PerfectTree<T, P, D>, right: PerfectTree<T, P, D>, zone: &mut Z)
+        -> Result<Self, (PerfectTree<T, P, D>, PerfectTree<T, P, D>)>
+        where Z: Alloc<Ptr = P>
+    {
+        let tip = Tip::try_join_in(left, right, zone)?;
+        Ok(Self::from(tip))
+    }
+
+    /// Creates a new leaf, allocating the value in `zone`.
+    #[track_caller]
+    pub fn new_leaf_in<Z>(value: T, zone: &mut Z) -> Self
+        where Z: Alloc<Ptr = P>
+    {
+        Self::from(Leaf::new_in(value, zone))
+    }
+
+    /// Creates a new leaf, returning the error if `zone` can't allocate.
+    pub fn try_new_leaf_in<Z>(value: T, zone: &mut Z) -> Result<Self, Z::Error>
+        where Z: Alloc<Ptr = P>
+    {
+        Leaf::try_new_in(value, zone).map(Self::from)
+    }
+}
+
+impl<T: Commit, D: Digest> PerfectTree<T, (), D> {
+    /// Calculates the commitment to a tree of borrowed leaves, without building the tree.
+    ///
+    /// Only the digests of each level are kept, so no leaf is moved or cloned. Returns `None` if
+    /// the number of leaves isn't a power of two.
+    pub fn commit_from_refs(leaves: &[&T]) -> Option<Commitment<T, D>> {
+        let height = Height::try_from_len(leaves.len())?;
+
+        let mut digests: Vec<D> = leaves.iter()
+                                        .map(|leaf| HashCommit::<T::Commitment, D>::new(*leaf).digest())
+                                        .collect();
+
+        for pair_height in (1 ..= height.get()).map(NonZeroHeight::from_u8) {
+            let pair_height = pair_height.expect("valid height");
+            digests = digests.chunks(2).map(|pair| {
+                let raw = raw::Pair::new(raw::Node::new(Some(pair[0]), ()),
+                                         raw::Node::new(Some(pair[1]), ()));
+                let commitment = unsafe { Pair::<T::Commitment, (), D>::from_raw_pair(raw, pair_height) };
+                HashCommit::from_blob_in::<()>(&commitment).digest()
+            }).collect();
+        }
+
+        let raw = raw::Node::new(Some(digests[0]), ());
+        Some(unsafe { PerfectTree::from_raw_node(raw, height) })
+    }
 }
 
 impl<T, P: Ptr, D: Digest> From<Leaf<T, P, D>> for PerfectTree<T, P, D> {
@@ -162,6 +301,120 @@ where T: Load,
             Kind::Tip(tip) => tip.into_get_leaf(idx),
         }
     }
+
+    /// Splits the tree into its left and right halves, or `None` if the tree is a single leaf.
+    pub fn into_halves(self) -> Option<(Self, Self)>
+        where P: Get
+    {
+        match self.into_kind() {
+            Kind::Leaf(_) => None,
+            Kind::Tip(tip) => Some(tip.into_get_pair().into_split()),
+        }
+    }
+
+    /// Moves the tree into `zone`, re-allocating every node.
+    ///
+    /// Digests are recalculated on demand in the new zone.
+    pub fn into_zone<Q: Alloc>(self, zone: &mut Q) -> PerfectTree<T, Q::Ptr, D>
+        where P: Get
+    {
+        match self.into_kind() {
+            Kind::Leaf(leaf) => PerfectTree::new_leaf_in(leaf.take(), zone),
+            Kind::Tip(tip) => {
+                let (left, right) = tip.into_get_pair().into_split();
+                let left = left.into_zone(zone);
+                let right = right.into_zone(zone);
+                PerfectTree::try_join_in(left, right, zone).ok().expect("moved trees have equal heights")
+            }
+        }
+    }
+
+    /// Maps every item in the tree, rebuilding it with the same shape.
+    ///
+    /// Items are mapped in order, and digests are recomputed for the new items.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> PerfectTree<U, P, D>
+        where P: Get + Default
+    {
+        self.map_impl(&mut f)
+    }
+
+    fn map_impl<U, F>(self, f: &mut F) -> PerfectTree<U, P, D>
+        where P: Get + Default,
+              F: FnMut(T) -> U,
+    {
+        match self.into_kind() {
+            Kind::Leaf(leaf) => PerfectTree::new_leaf(f(leaf.take())),
+            Kind::Tip(tip) => {
+                let (left, right) = tip.into_get_pair().into_split();
+                let left = left.map_impl(f);
+                let right = right.map_impl(f);
+                PerfectTree::try_join(left, right).ok().expect("mapped trees have equal heights")
+            }
+        }
+    }
+
+    /// Drops every item, keeping the shape of the tree and all of its digests.
+    ///
+    /// The leaves of the pruned tree hold `()` along with the digests of the original items, so
+    /// its root digest is unchanged and proofs made from the original tree verify against it.
+    pub fn prune_values(self) -> PerfectTree<(), P, D>
+        where T: Commit,
+              P: Get + Default
+    {
+        // Calculates and caches every digest in the tree
+        let digest = self.node_digest();
+
+        match self.into_kind() {
+            Kind::Leaf(_) => Leaf::new_unchecked(Some(digest), P::alloc(())).into(),
+            Kind::Tip(tip) => {
+                let (left, right) = tip.into_get_pair().into_split();
+                let pair = Pair::try_join(left.prune_values(), right.prune_values())
+                                .ok().expect("pruned trees have equal heights");
+                Tip::new_unchecked(Some(digest), P::alloc(pair)).into()
+            }
+        }
+    }
+}
+
+/// Returned by `PerfectTree::join_and_save` when the two trees can't be joined.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum JoinError {
+    #[error("height mismatch: left tree has height {left}, right tree has height {right}")]
+    HeightMismatch {
+        left: Height,
+        right: Height,
+    },
+
+    #[error("joined tree would be taller than the maximum height")]
+    TooTall,
+}
+
+impl<'m, T, D: Digest> PerfectTree<T, PileMut<'m, [u8]>, D>
+where T: Load,
+      &'m [u8]: AsZone<T::Zone>,
+{
+    /// Joins the tree saved at `left_root` with `right`, saving only the new nodes.
+    ///
+    /// The left tree is loaded once and reused as-is; see `offset::join_and_save`. `right` can be
+    /// in any zone: it's moved into the pile with `into_zone`, so all of it is saved.
+    ///
+    /// Returns the offset of the joined tree and the bytes to append to `pile`.
+    pub fn join_and_save<Q: Get>(pile: &'m [u8], left_root: Offset, right: PerfectTree<T, Q, D>)
+        -> Result<(Offset, Vec<u8>), JoinAndSaveError<JoinError>>
+        where Q::Zone: AsZone<T::Zone>,
+              Self: Save<Offset>,
+              Key<'m, [u8]>: From<<Self as Load>::PtrClean>,
+              &'m [u8]: AsZone<<<Self as Load>::PtrClean as PtrClean>::Zone>,
+    {
+        offset::join_and_save(pile, left_root, right, |left: Self, right| {
+            if left.height() != right.height() {
+                return Err(JoinError::HeightMismatch { left: left.height(), right: right.height() });
+            }
+            let mut zone = pile;
+            let right = right.into_zone(&mut zone);
+            Self::try_join(left, right).map_err(|_| JoinError::TooTall)
+        })
+    }
 }
 
 impl<T, P: Ptr, D: Digest> PerfectTreeDyn<T, P, D>
@@ -179,6 +432,92 @@ where T: Load,
         })
     }
 
+    /// Checks that the items are sorted according to `cmp`.
+    ///
+    /// The tree is walked depth first, comparing each item to the one before it and stopping at
+    /// the first pair where the left item is greater than the right. Adjacent items in different
+    /// halves of a pair are found by walking down the inner edges of the halves, which loads each
+    /// pair at most three times, so this is O(n) and allocates nothing.
+    pub fn is_sorted_by(&self, mut cmp: impl FnMut(&T, &T) -> cmp::Ordering) -> bool
+        where P: Get
+    {
+        self.is_sorted_by_impl(&mut cmp)
+    }
+
+    fn is_sorted_by_impl<F>(&self, cmp: &mut F) -> bool
+        where P: Get,
+              F: FnMut(&T, &T) -> cmp::Ordering
+    {
+        match self.kind() {
+            Kind::Leaf(_) => true,
+            Kind::Tip(tip) => {
+                let pair = tip.get_pair();
+                let (left, right) = (pair.left(), pair.right());
+                left.is_sorted_by_impl(cmp)
+                    && left.with_edge_item(true, |last| {
+                           right.with_edge_item(false, |first| cmp(last, first) != cmp::Ordering::Greater)
+                       })
+                    && right.is_sorted_by_impl(cmp)
+            },
+        }
+    }
+
+    /// Calls `f` with the last item of the tree if `last` is true, otherwise the first.
+    fn with_edge_item<R>(&self, last: bool, f: impl FnOnce(&T) -> R) -> R
+        where P: Get
+    {
+        match self.kind() {
+            Kind::Leaf(leaf) => f(&leaf.get()),
+            Kind::Tip(tip) => {
+                let pair = tip.get_pair();
+                let half = if last { pair.right() } else { pair.left() };
+                half.with_edge_item(last, f)
+            },
+        }
+    }
+
+    /// Returns the range of pile bytes spanned by the blobs of this tree.
+    ///
+    /// The range covers the blob of every leaf value and pair in the tree, but not the tree's own
+    /// node, which is stored in its parent. Blobs that the leaf values themselves point to, eg. if
+    /// `T` is another collection, aren't followed, as only their pointers are visible here. So for
+    /// a tree saved in one go this is exactly the bytes saved for it only if `T` contains no
+    /// pointers; otherwise those blobs are saved before the leaf values and may lie outside the
+    /// range.
+    ///
+    /// Offsets are read from the tree's own clean pointers, so no zone argument is needed.
+    ///
+    /// Returns `None` if any part of the tree is dirty.
+    pub fn blob_range(&self) -> Option<Range<usize>>
+        where P: Get,
+              P::Blob: Into<Offset>,
+    {
+        match self.kind() {
+            Kind::Leaf(leaf) => {
+                let offset: Offset = leaf.try_get_dirty().err()?.to_blob().into();
+                Some(offset.region(<T::Blob as Blob>::SIZE))
+            },
+            Kind::Tip(tip) => {
+                let offset: Offset = tip.try_get_dirty_pair().err()?.to_blob().into();
+                let range = offset.region(<raw::Pair<T::Blob, P::Blob, D> as Blob>::SIZE);
+
+                let pair = tip.get_pair();
+                let (left, right) = (pair.left().blob_range()?, pair.right().blob_range()?);
+                Some(range.start.min(left.start).min(right.start) .. range.end.max(left.end).max(right.end))
+            },
+        }
+    }
+
+    /// Gets an owned copy of the item at `idx`, leaving the tree intact.
+    ///
+    /// Items loaded from a zone are already owned, so they're returned without cloning.
+    pub fn get_owned(&self, idx: usize) -> Option<T>
+        where P: Get,
+              T: Clone,
+    {
+        self.get(idx).map(Ref::into_owned)
+    }
+
     pub fn get_leaf(&self, idx: usize) -> Option<Ref<Leaf<T, P, D>>>
         where P: Get
     {
@@ -188,6 +527,278 @@ where T: Load,
             Kind::Tip(tip) => tip.get_leaf(idx),
         }
     }
+
+    /// Gets a mutable reference to the leaf at `idx`.
+    ///
+    /// The digests of every node on the path to the leaf are cleared.
+    pub fn get_leaf_mut(&mut self, idx: usize) -> Option<&mut Leaf<T, P, D>>
+        where P: GetMut
+    {
+        if idx >= self.height().len() {
+            return None;
+        }
+
+        match self.kind_mut() {
+            Kind::Leaf(leaf) => Some(leaf),
+            Kind::Tip(tip) => tip.get_leaf_mut(idx),
+        }
+    }
+
+    /// Replaces the item at `idx`, returning the old item.
+    ///
+    /// If `idx` is out of range, `value` is returned as an error. Only the digests on the path to
+    /// the leaf are cleared, so recalculating the root afterwards takes `height` hashes.
+    pub fn set_leaf(&mut self, idx: usize, value: T) -> Result<T, T>
+        where P: GetMut
+    {
+        match self.get_leaf_mut(idx) {
+            Some(leaf) => Ok(mem::replace(leaf.get_mut(), value)),
+            None => Err(value),
+        }
+    }
+
+    /// Compares the leaf values of two trees, possibly in different zones.
+    ///
+    /// Unlike `==`, which compares digests, every leaf is loaded and compared by value, stopping at
+    /// the first mismatch. Trees of different lengths are never equal.
+    pub fn contents_eq<Q: Ptr>(&self, other: &PerfectTreeDyn<T, Q, D>) -> bool
+        where T: PartialEq,
+              P: Get,
+              Q: Get,
+              Q::Zone: AsZone<T::Zone>,
+    {
+        if self.height() != other.height() {
+            return false;
+        }
+
+        match (self.kind(), other.kind()) {
+            (Kind::Leaf(lhs), Kind::Leaf(rhs)) => *lhs.get() == *rhs.get(),
+            (Kind::Tip(lhs), Kind::Tip(rhs)) => {
+                let (lhs, rhs) = (lhs.get_pair(), rhs.get_pair());
+                lhs.left().contents_eq(rhs.left()) && lhs.right().contents_eq(rhs.right())
+            },
+            _ => unreachable!("heights equal"),
+        }
+    }
+
+    /// Folds every leaf, in order, into an accumulator.
+    ///
+    /// The tree is walked depth first, loading each pair once.
+    pub fn fold<B, F>(&self, init: B, mut f: F) -> B
+        where P: Get,
+              F: FnMut(B, Ref<'_, Leaf<T, P, D>>) -> B
+    {
+        self.fold_impl(init, &mut f)
+    }
+
+    fn fold_impl<B, F>(&self, acc: B, f: &mut F) -> B
+        where P: Get,
+              F: FnMut(B, Ref<'_, Leaf<T, P, D>>) -> B
+    {
+        match self.kind() {
+            Kind::Leaf(leaf) => f(acc, Ref::Borrowed(leaf)),
+            Kind::Tip(tip) => {
+                let pair = tip.get_pair();
+                let acc = pair.left().fold_impl(acc, f);
+                pair.right().fold_impl(acc, f)
+            },
+        }
+    }
+}
+
+impl<T, P: Ptr, D: Digest> PerfectTreeDyn<T, P, D>
+where T: Commit + Load,
+      P: Get,
+      P::Zone: AsZone<T::Zone>,
+{
+    /// Calculates the root digest within the domain `C`.
+    ///
+    /// Every leaf and pair digest is recalculated with the domain tag. Domain digests are not
+    /// cached, so in the default domain prefer `node_digest`, which this is equal to.
+    pub fn node_digest_in<C: CommitContext>(&self) -> D {
+        match self.kind() {
+            Kind::Leaf(leaf) => HashCommit::<T::Commitment, D>::new_in::<C, _>(&*leaf.get()).digest(),
+            Kind::Tip(tip) => tip.get_pair().pair_digest_in::<C>(),
+        }
+    }
+
+    /// Returns the digest of the node at `height`, `index` nodes from the left, calculating it if
+    /// necessary.
+    ///
+    /// Height zero is the leaves, and the tree's own height is the root. Returns `None` if there's
+    /// no node at those coordinates.
+    pub fn digest_at(&self, height: Height, index: usize) -> Option<D> {
+        let depth = self.height().get().checked_sub(height.get())?;
+        if index >> depth != 0 {
+            return None;
+        }
+        Some(self.digest_at_depth(depth, index))
+    }
+
+    fn digest_at_depth(&self, depth: u8, index: usize) -> D {
+        match self.kind() {
+            Kind::Tip(tip) if depth > 0 => {
+                let pair = tip.get_pair();
+                let half = 1 << (depth - 1);
+                if index < half {
+                    pair.left().digest_at_depth(depth - 1, index)
+                } else {
+                    pair.right().digest_at_depth(depth - 1, index - half)
+                }
+            },
+            _ => self.node_digest(),
+        }
+    }
+
+    /// Finds the index of the first leaf whose digest is `target`.
+    ///
+    /// Inner node digests say nothing about which leaves they contain, so this is O(n): every leaf
+    /// is loaded and hashed until a match is found. Leaf digests are cached, making repeated
+    /// searches cheaper.
+    pub fn find_by_digest(&self, target: &D) -> Option<usize> {
+        self.find_by_digest_impl(target, 0)
+    }
+
+    fn find_by_digest_impl(&self, target: &D, offset: usize) -> Option<usize> {
+        match self.kind() {
+            Kind::Leaf(leaf) if leaf.digest() == *target => Some(offset),
+            Kind::Leaf(_) => None,
+            Kind::Tip(tip) => {
+                let pair = tip.get_pair();
+                pair.left().find_by_digest_impl(target, offset)
+                    .or_else(|| pair.right().find_by_digest_impl(target, offset + pair.left().height().len()))
+            },
+        }
+    }
+
+    /// Returns every pair of indices of leaves with equal digests, ie. duplicate items.
+    ///
+    /// Pairs are returned sorted, with the lower index first. Every leaf is loaded and hashed
+    /// once, and duplicates are found with a hash map, so this is O(n) plus the number of pairs.
+    pub fn duplicate_positions(&self) -> Vec<(usize, usize)>
+        where D: hash::Hash
+    {
+        let mut digests = Vec::with_capacity(self.height().len());
+        self.leaf_digests_in(&mut digests);
+
+        let mut seen: HashMap<D, Vec<usize>> = HashMap::new();
+        let mut dups = vec![];
+        for (idx, digest) in digests.into_iter().enumerate() {
+            let prev = seen.entry(digest).or_default();
+            dups.extend(prev.iter().map(|&prev_idx| (prev_idx, idx)));
+            prev.push(idx);
+        }
+        dups.sort_unstable();
+        dups
+    }
+
+    fn leaf_digests_in(&self, digests: &mut Vec<D>) {
+        match self.kind() {
+            Kind::Leaf(leaf) => digests.push(leaf.digest()),
+            Kind::Tip(tip) => {
+                let pair = tip.get_pair();
+                pair.left().leaf_digests_in(digests);
+                pair.right().leaf_digests_in(digests);
+            },
+        }
+    }
+
+    /// Returns the indices of the leaves that differ between two trees of the same height.
+    ///
+    /// Subtrees with equal digests are skipped without being loaded, so only O(d log n) nodes are
+    /// visited for d differences. Indices are returned in ascending order.
+    ///
+    /// The bound assumes the digests are already cached, as they are in trees loaded from a pile.
+    /// Missing digests are calculated first, hashing every node that lacks one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the trees have different heights.
+    pub fn diff(&self, other: &Self) -> Vec<usize> {
+        assert_eq!(self.height(), other.height(), "trees must have the same height");
+        let mut diffs = vec![];
+        self.diff_impl(other, 0, &mut diffs);
+        diffs
+    }
+
+    fn diff_impl(&self, other: &Self, offset: usize, diffs: &mut Vec<usize>) {
+        if self.node_digest() == other.node_digest() {
+            return;
+        }
+
+        match (self.kind(), other.kind()) {
+            (Kind::Tip(tip), Kind::Tip(other_tip)) => {
+                let (pair, other_pair) = (tip.get_pair(), other_tip.get_pair());
+                pair.left().diff_impl(other_pair.left(), offset, diffs);
+                pair.right().diff_impl(other_pair.right(), offset + pair.left().height().len(), diffs);
+            },
+            _ => diffs.push(offset),
+        }
+    }
+
+    /// Calculates the root digest with the tree hashing scheme `S`, eg. `Rfc6962`.
+    ///
+    /// Like `node_digest_in`, every digest is recalculated rather than cached.
+    pub fn node_digest_with<S: TreeHash<D>>(&self) -> D {
+        match self.kind() {
+            Kind::Leaf(leaf) => S::hash_leaf(&*leaf.get()),
+            Kind::Tip(tip) => {
+                let pair = tip.get_pair();
+                S::hash_node(&pair.left().node_digest_with::<S>(),
+                             &pair.right().node_digest_with::<S>())
+            }
+        }
+    }
+
+    /// Draws the tree as an indented ASCII diagram, one node per line.
+    ///
+    /// Each line shows the node's height or leaf value, followed by the first few bytes of its
+    /// digest. Meant for inspecting small trees while debugging; the format isn't stable.
+    pub fn draw(&self) -> String
+        where T: fmt::Debug
+    {
+        let mut dst = String::new();
+        self.draw_impl(&mut dst, 0);
+        dst
+    }
+
+    fn draw_impl(&self, dst: &mut String, depth: usize)
+        where T: fmt::Debug
+    {
+        use std::fmt::Write as _;
+
+        let digest: String = self.node_digest().as_ref().iter()
+                                 .take(4)
+                                 .map(|b| format!("{:02x}", b))
+                                 .collect();
+        let indent = depth * 2;
+
+        match self.kind() {
+            Kind::Leaf(leaf) => {
+                writeln!(dst, "{:indent$}leaf {:?} {}", "", &*leaf.get(), digest, indent = indent).unwrap();
+            },
+            Kind::Tip(tip) => {
+                writeln!(dst, "{:indent$}tip {} {}", "", tip.height(), digest, indent = indent).unwrap();
+
+                let pair = tip.get_pair();
+                pair.left().draw_impl(dst, depth + 1);
+                pair.right().draw_impl(dst, depth + 1);
+            },
+        }
+    }
+}
+
+impl<T, P: Ptr, D: Digest> PairDyn<T, P, D>
+where T: Commit + Load,
+      P: Get,
+      P::Zone: AsZone<T::Zone>,
+{
+    fn pair_digest_in<C: CommitContext>(&self) -> D {
+        let raw = raw::Pair::new(raw::Node::new(Some(self.left().node_digest_in::<C>()), ()),
+                                 raw::Node::new(Some(self.right().node_digest_in::<C>()), ()));
+        let commitment = unsafe { Pair::<T::Commitment, (), D>::from_raw_pair(raw, self.height()) };
+        HashCommit::from_blob_in::<C>(&commitment).digest()
+    }
 }
 
 impl<T, P: Ptr, D: Digest> PerfectTreeDyn<T, P, D> {
@@ -199,6 +810,32 @@ impl<T, P: Ptr, D: Digest> PerfectTreeDyn<T, P, D> {
         NonZeroLength::from_height(self.height())
     }
 
+    /// Checks that the tree contains exactly `expected` items.
+    ///
+    /// Only the height is checked, so this never loads anything.
+    pub fn assert_len(&self, expected: usize) -> Result<&Self, LenMismatch> {
+        let found = usize::from(self.len());
+        if found == expected {
+            Ok(self)
+        } else {
+            Err(LenMismatch { expected, found })
+        }
+    }
+
+    /// Counts the nodes that haven't been saved yet, ie. the blobs a save would write.
+    ///
+    /// A node is dirty if its pointer is dirty: every node of a freshly built `Heap` tree, and no
+    /// node of a tree loaded from a pile. Clean subtrees are skipped without being loaded.
+    pub fn dirty_node_count(&self) -> usize {
+        match self.kind() {
+            Kind::Leaf(leaf) => leaf.try_get_dirty().is_ok() as usize,
+            Kind::Tip(tip) => match tip.try_get_dirty_pair() {
+                Ok(pair) => 1 + pair.left().dirty_node_count() + pair.right().dirty_node_count(),
+                Err(_) => 0,
+            },
+        }
+    }
+
     pub fn kind(&self) -> Kind<&Leaf<T, P, D>, &TipDyn<T, P, D>> {
         if let Ok(height) = NonZeroHeight::try_from(self.height()) {
             let tip = unsafe { TipDyn::from_raw_node_ref(&self.raw, height) };
@@ -219,24 +856,53 @@ impl<T, P: Ptr, D: Digest> PerfectTreeDyn<T, P, D> {
         }
     }
 
-    /*
-    pub fn node_digest(&self) -> Digest
+    /// Returns the digest of the root node, re-hashing if necessary.
+    pub fn node_digest(&self) -> D
         where T: Commit
     {
         match self.kind() {
-            Kind::Leaf(leaf) => leaf.digest().cast(),
-            Kind::Tip(tip) => tip.pair_digest().cast(),
+            Kind::Leaf(leaf) => leaf.value_commit().digest(),
+            Kind::Tip(tip) => tip.pair_commit().digest(),
         }
     }
 
-    pub fn try_node_digest(&self) -> Option<Digest>
+    /// Returns the digest of the root node, if already available.
+    pub fn try_node_digest(&self) -> Option<D> {
+        self.raw.digest()
+    }
+
+    /// Returns the root digest of the tree's commitment.
+    ///
+    /// ```
+    /// # use hoard::ptr::Heap;
+    /// # use proofmarshal_core::collections::perfecttree::{PerfectTree, Commitment};
+    /// # use proofmarshal_core::commit::Commit;
+    /// let tree = PerfectTree::<u8, Heap>::try_join(PerfectTree::new_leaf(1),
+    ///                                              PerfectTree::new_leaf(2)).unwrap();
+    /// let commitment: Commitment<u8> = tree.to_commitment();
+    /// assert_eq!(tree.root_digest(), commitment.node_digest());
+    /// ```
+    pub fn root_digest(&self) -> D
+        where T: Commit
     {
-        match self.kind() {
-            Kind::Leaf(leaf) => leaf.try_digest(),
-            Kind::Tip(tip) => tip.try_pair_digest(),
-        }
+        self.to_commitment()
+            .try_node_digest()
+            .expect("commitments always have digests")
+    }
+
+    /// Checks that the root digest of the tree is `expected`.
+    ///
+    /// Missing digests are calculated and cached, as with `node_digest`, so verifying a tree
+    /// again is cheap. The digests are compared in constant time.
+    pub fn verify_root(&self, expected: &D) -> bool
+        where T: Commit
+    {
+        let actual = self.node_digest();
+        let (actual, expected) = (actual.as_ref(), expected.as_ref());
+
+        actual.len() == expected.len()
+            && actual.iter().zip(expected).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
     }
-    */
 }
 
 impl<T, P: Ptr, D: Digest> Tip<T, P, D> {
@@ -253,9 +919,38 @@ impl<T, P: Ptr, D: Digest> Tip<T, P, D> {
         Self::new_unchecked(None, P::alloc(pair))
     }
 
+    pub fn try_join_in<Z>(left: PerfectTree<T, P, D>, right: PerfectTree<T, P, D>, zone: &mut Z)
+        -> Result<Self, (PerfectTree<T, P, D>, PerfectTree<T, P, D>)>
+        where Z: Alloc<Ptr = P>
+    {
+        let pair = Pair::try_join(left, right)?;
+        Ok(Self::new_in(pair, zone))
+    }
+
+    #[track_caller]
+    pub fn new_in<Z>(pair: Pair<T, P, D>, zone: &mut Z) -> Self
+        where Z: Alloc<Ptr = P>
+    {
+        Self::try_new_in(pair, zone).expect("allocation failed")
+    }
+
+    /// Creates a new tip from a pair, returning the error if `zone` can't allocate.
+    pub fn try_new_in<Z>(pair: Pair<T, P, D>, zone: &mut Z) -> Result<Self, Z::Error>
+        where Z: Alloc<Ptr = P>
+    {
+        Ok(Self::new_unchecked(None, zone.try_alloc(pair)?))
+    }
+
+    /// Creates a new tip from a pair, without checking that `digest` is correct.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if `pair` is clean but `digest` is `None`: the pair couldn't be
+    /// hashed later without loading it.
+    #[track_caller]
     pub fn new_unchecked(digest: Option<D>, pair: Bag<PairDyn<T, P, D>, P>) -> Self {
         let (ptr, height) = pair.into_raw_parts();
-        let raw = raw::Node::new(digest, ptr);
+        let raw = raw::Node::new_checked(digest, ptr);
 
         unsafe {
             Self::from_raw_node(raw, height)
@@ -299,6 +994,16 @@ where T: Load,
         }
     }
 
+    pub fn get_leaf_mut(&mut self, idx: usize) -> Option<&mut Leaf<T, P, D>>
+        where P: GetMut
+    {
+        if idx < Height::from(self.height()).len() {
+            self.get_pair_mut().get_leaf_mut(idx)
+        } else {
+            None
+        }
+    }
+
     pub fn get_pair(&self) -> Ref<PairDyn<T, P, D>>
         where P: Get
     {
@@ -317,6 +1022,66 @@ where T: Load,
                     .trust()
         }
     }
+
+    /// Gets the pair owned by this tip, checking it against the stored digest.
+    ///
+    /// Unlike `get_pair`, the pair commitment is recomputed from the loaded pair and compared to
+    /// the digest stored in the tip, making corruption of the underlying zone detectable.
+    pub fn get_pair_verified(&self) -> Result<Ref<PairDyn<T, P, D>>, PairDigestMismatch<D>>
+        where P: Get,
+              T: Commit,
+    {
+        let pair = self.get_pair();
+        if let Some(expected) = self.raw.digest() {
+            let actual = HashCommit::<Pair<T::Commitment, (), D>, D>::new(&*pair).digest();
+            if actual != expected {
+                return Err(PairDigestMismatch { expected, actual });
+            }
+        }
+        Ok(pair)
+    }
+
+    /// Loads a tip from its blob bytes, checking the stored digest against the pair it points to.
+    ///
+    /// Loading through `LoadRef` trusts the stored digest as-is; this is the opt-in verified
+    /// alternative for zones that may be corrupt.
+    pub fn load_verified(src: Bytes<'_, <Self as LoadRef>::BlobDyn>, zone: &P::Zone)
+        -> Result<Tip<T, P, D>, LoadVerifiedError<<<Self as LoadRef>::BlobDyn as BlobDyn>::DecodeBytesError, D>>
+        where P: Get,
+              T: Commit,
+    {
+        let tip = Self::load_owned_from_bytes(src, zone)
+                      .map_err(LoadVerifiedError::Decode)?
+                      .trust();
+        tip.get_pair_verified().map_err(LoadVerifiedError::Mismatch)?;
+        Ok(tip)
+    }
+}
+
+/// Returned by `TipDyn::get_pair_verified` when the loaded pair doesn't match the tip digest.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("pair digest mismatch")]
+pub struct PairDigestMismatch<D> {
+    pub expected: D,
+    pub actual: D,
+}
+
+/// Returned by `TipDyn::load_verified`.
+#[derive(Debug, Error)]
+pub enum LoadVerifiedError<Decode: error::Error, D> {
+    #[error("failed to decode tip")]
+    Decode(#[source] Decode),
+
+    #[error("pair digest mismatch")]
+    Mismatch(PairDigestMismatch<D>),
+}
+
+/// Returned by `PerfectTreeDyn::assert_len` when a tree has the wrong number of items.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("expected {expected} items, found {found}")]
+pub struct LenMismatch {
+    pub expected: usize,
+    pub found: usize,
 }
 
 impl<T, P: Ptr, D: Digest> TipDyn<T, P, D> {
@@ -336,9 +1101,38 @@ impl<T, P: Ptr, D: Digest> TipDyn<T, P, D> {
     fn calc_pair_commit(&self) -> HashCommit<Pair<T::Commitment, (), D>, D>
         where T: Commit
     {
-        let pair = self.try_get_dirty_pair()
-                       .ok().expect("digest missing yet tip ptr clean");
-        let hash_commit = HashCommit::new(pair);
+        // Hashing a pair needs the digests of both children, which would otherwise be
+        // calculated recursively. Instead missing digests are calculated post-order with an
+        // explicit stack, so every pair is hashed with its children's digests already cached,
+        // and the call depth doesn't grow with the height of the tree.
+        let mut stack: Vec<(&Self, bool)> = vec![(self, false)];
+        while let Some((tip, children_done)) = stack.pop() {
+            if tip.try_pair_commit().is_some() {
+                continue;
+            } else if children_done {
+                tip.hash_pair();
+                continue;
+            }
+
+            stack.push((tip, true));
+            let pair = tip.try_get_dirty_pair()
+                          .ok().expect("digest missing yet tip ptr clean");
+            for child in [pair.right(), pair.left()].iter().copied() {
+                if let Kind::Tip(child) = child.kind() {
+                    stack.push((child, false));
+                }
+            }
+        }
+
+        self.try_pair_commit().expect("pair digest calculated")
+    }
+
+    fn hash_pair(&self) -> HashCommit<Pair<T::Commitment, (), D>, D>
+        where T: Commit
+    {
+        let pair = self.try_get_dirty_pair()
+                       .ok().expect("digest missing yet tip ptr clean");
+        let hash_commit = HashCommit::new(pair);
         self.raw.set_digest(hash_commit.digest());
         hash_commit
     }
@@ -356,10 +1150,7 @@ impl<T, P: Ptr, D: Digest> Pair<T, P, D> {
         if left.height() != right.height() {
             panic!("height mismatch")
         } else if let Some(height) = left.height().try_increment() {
-            let pair = raw::Pair {
-                left: left.into_raw_node(),
-                right: right.into_raw_node(),
-            };
+            let pair = raw::Pair::new(left.into_raw_node(), right.into_raw_node());
 
             Ok(unsafe { Self::from_raw_pair(pair, height) })
         } else {
@@ -403,6 +1194,19 @@ where T: Load,
             None
         }
     }
+
+    pub fn get_leaf_mut(&mut self, idx: usize) -> Option<&mut Leaf<T, P, D>>
+        where P: GetMut
+    {
+        let len = usize::from(self.len());
+        if idx < len / 2 {
+            self.left_mut().get_leaf_mut(idx)
+        } else if idx < len {
+            self.right_mut().get_leaf_mut(idx - (len / 2))
+        } else {
+            None
+        }
+    }
 }
 
 impl<T, P: Ptr, D: Digest> Pair<T, P, D> {
@@ -450,6 +1254,13 @@ impl<T, P: Ptr, D: Digest> PairDyn<T, P, D> {
             PerfectTreeDyn::from_raw_node_mut(&mut self.raw.right, height)
         }
     }
+
+    /// Returns the digests of the left and right children, calculating them if necessary.
+    pub fn child_digests(&self) -> (D, D)
+        where T: Commit
+    {
+        (self.left().node_digest(), self.right().node_digest())
+    }
 }
 
 // --------- conversions from raw -------------
@@ -511,6 +1322,33 @@ impl<T, P: Ptr, D: Digest> TipDyn<T, P, D> {
     }
 }
 
+impl<T, P: Ptr, D: Digest> PerfectTree<T, P, D> {
+    /// Returns the number of bytes saving the tree to a pile would take, without saving it.
+    ///
+    /// Every blob is encoded to a scratch buffer and counted. As with `save_new`, only trees with
+    /// no clean pointers can be measured.
+    pub fn serialized_size(&self) -> usize
+        where Self: SaveRef<Offset>,
+              for<'m> Key<'m, [u8]>: From<<Self as LoadRef>::PtrClean>,
+              for<'m> &'m [u8]: AsZone<<<Self as LoadRef>::PtrClean as PtrClean>::Zone>,
+    {
+        let mut saver = SizeSaver::new();
+        save_dyn(EMPTY_PILE, &mut saver, self).expect("dirty trees can always be saved");
+        saver.size() as usize
+    }
+}
+
+impl<T, D: Digest> PerfectTree<T, (), D> {
+    /// Creates a commitment to a tree of `height` from its root digest alone.
+    ///
+    /// This is the same form `to_commitment` returns: the digest is stored, and there are no
+    /// children behind the `()` pointer. Useful as an anchor for verifying proofs.
+    pub fn commitment_stub(root: D, height: Height) -> Self {
+        let raw = raw::Node::new(Some(root), ());
+        unsafe { Self::from_raw_node(raw, height) }
+    }
+}
+
 impl<T, P: Ptr, D: Digest> PerfectTree<T, P, D> {
     pub unsafe fn from_raw_node(raw: raw::Node<T, P, D>, height: Height) -> Self {
         Self {
@@ -539,26 +1377,39 @@ impl<T, P: Ptr, D: Digest> PerfectTreeDyn<T, P, D> {
 // ------- pointee impls ----------
 
 macro_rules! impl_pointee {
-    ($t:ident, $meta_ty:ty) => {
+    ($t:ident, $meta_ty:ty, $raw:ident) => {
         impl<T, P: Ptr, D: Digest> Pointee for $t<T, P, D> {
             type Metadata = $meta_ty;
-            type LayoutError = !;
+            type LayoutError = HeightError;
+
+            fn try_layout(height: Self::Metadata) -> Result<Layout, HeightError> {
+                // The height is stored as a zero-sized slice, so only the raw node takes up space
+                if height <= Height::MAX {
+                    Ok(Layout::new::<ManuallyDrop<raw::$raw<T, P, D>>>())
+                } else {
+                    Err(HeightError)
+                }
+            }
 
             fn metadata(ptr: *const Self) -> Self::Metadata {
-                unsafe {
-                    let ptr: *const [()] = mem::transmute(ptr);
-                    let len: usize = ptr.len();
+                Self::try_metadata(ptr).unwrap_or_else(|_|
+                    unsafe { unreachable_unchecked!("invalid metadata") }
+                )
+            }
 
-                    <$meta_ty>::try_from(len)
-                               .unwrap_or_else(|_|
-                                   unreachable_unchecked!("invalid metadata")
-                               )
-                }
+            fn try_metadata(ptr: *const Self) -> Result<Self::Metadata, MetadataError> {
+                let ptr: *const [()] = unsafe { mem::transmute(ptr) };
+                let len: usize = ptr.len();
+
+                <$meta_ty>::try_from(len).map_err(|_| MetadataError)
             }
 
             fn make_fat_ptr(thin: *const (), height: Self::Metadata) -> *const Self {
                 let height = height.get();
                 let height: u8 = height.into();
+
+                // Metadata from untrusted sources is validated on decode, so this is an invariant
+                debug_assert!(height <= Height::MAX, "invalid height metadata: {}", height);
                 let ptr = ptr::slice_from_raw_parts(thin, height.into());
                 unsafe { mem::transmute(ptr) }
             }
@@ -566,6 +1417,9 @@ macro_rules! impl_pointee {
             fn make_fat_ptr_mut(thin: *mut (), height: Self::Metadata) -> *mut Self {
                 let height = height.get();
                 let height: u8 = height.into();
+
+                // Metadata from untrusted sources is validated on decode, so this is an invariant
+                debug_assert!(height <= Height::MAX, "invalid height metadata: {}", height);
                 let ptr = ptr::slice_from_raw_parts_mut(thin, height.into());
                 unsafe { mem::transmute(ptr) }
             }
@@ -573,9 +1427,9 @@ macro_rules! impl_pointee {
     }
 }
 
-impl_pointee!(PerfectTreeDyn, Height);
-impl_pointee!(TipDyn, NonZeroHeight);
-impl_pointee!(PairDyn, NonZeroHeight);
+impl_pointee!(PerfectTreeDyn, Height, Node);
+impl_pointee!(TipDyn, NonZeroHeight, Node);
+impl_pointee!(PairDyn, NonZeroHeight, Pair);
 
 // --------- deref impls ----------
 
@@ -614,11 +1468,14 @@ macro_rules! impl_deref {
             type Owned = $t<T, P, D>;
 
             fn into_owned(self: RefOwn<'_, Self>) -> Self::Owned {
+                // Nothing may run between the leak and the read: if it panicked, the value
+                // would be neither dropped by the RefOwn, nor owned by the result.
+                let height = self.height();
                 let this = RefOwn::leak(self);
                 unsafe {
                     $t {
                         marker: PhantomData,
-                        height: this.height(),
+                        height,
                         raw: ptr::read(&this.raw),
                     }
                 }
@@ -678,6 +1535,83 @@ where T: 'static,
     }
 }
 
+/// Returned by `PerfectTree::decode_self_describing`.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DecodeSelfDescribingError<Raw: error::Error> {
+    #[error("not a perfect tree blob")]
+    Tag,
+
+    #[error("expected {expected} bytes, found {found}")]
+    Length { expected: usize, found: usize },
+
+    #[error("invalid height")]
+    Height(#[from] HeightError),
+
+    #[error("invalid node")]
+    Raw(Raw),
+}
+
+impl<T, P: Ptr, D: Digest> PerfectTree<T, P, D>
+where T: 'static,
+      P: Blob,
+{
+    /// Type tag at the start of every self-describing blob.
+    pub const SELF_DESCRIBING_TAG: [u8; 4] = *b"ptre";
+
+    const SELF_DESCRIBING_SIZE: usize = 4 + 1 + <raw::Node<T, P, D> as Blob>::SIZE;
+
+    /// Encodes the tree as a standalone blob, prefixed with a type tag and the height.
+    ///
+    /// Unlike the `Blob` encoding, readers can identify the blob without knowing its type in
+    /// advance.
+    pub fn encode_self_describing(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::SELF_DESCRIBING_SIZE);
+        buf.extend_from_slice(&Self::SELF_DESCRIBING_TAG);
+        buf.push(self.height().get());
+        buf.extend_from_slice(&self.raw.to_blob_bytes());
+        buf
+    }
+
+    /// Decodes a blob written by `encode_self_describing`.
+    ///
+    /// Heights above `Height::MAX` are rejected, so a blob written on a 64-bit target may fail to
+    /// decode on a 32-bit one.
+    pub fn decode_self_describing(buf: &[u8])
+        -> Result<Self, DecodeSelfDescribingError<<raw::Node<T, P, D> as Blob>::DecodeBytesError>>
+    {
+        if !buf.starts_with(&Self::SELF_DESCRIBING_TAG) {
+            return Err(DecodeSelfDescribingError::Tag);
+        } else if buf.len() != Self::SELF_DESCRIBING_SIZE {
+            return Err(DecodeSelfDescribingError::Length {
+                expected: Self::SELF_DESCRIBING_SIZE,
+                found: buf.len(),
+            });
+        }
+
+        let height = Height::try_from(buf[4])?;
+        let bytes = Bytes::<raw::Node<T, P, D>>::try_from(&buf[5 ..]).expect("length checked above");
+        let raw = <raw::Node<T, P, D> as Blob>::decode_bytes(bytes)
+                      .map_err(DecodeSelfDescribingError::Raw)?
+                      .trust();
+        Ok(unsafe { Self::from_raw_node(raw, height) })
+    }
+}
+
+impl<T, P: PtrBlob, D: Digest> PerfectTree<T, P, D> {
+    /// Converts a tree in blob form into a tree of `U` values behind `Q` pointers, in `zone`.
+    ///
+    /// Blob-form trees are what `Blob::decode_bytes` returns: their pointers are raw
+    /// `PtrBlob`s, eg. `Offset`, that can't be dereferenced until they're given a zone. This is
+    /// the same conversion `Load::load` does, as a method of the blob-form tree.
+    pub fn hydrate<U, Q>(self, zone: &Q::Zone) -> PerfectTree<U, Q, D>
+        where Q: Ptr<Blob = P>,
+              PerfectTree<U, Q, D>: Load<Blob = Self, Zone = Q::Zone>,
+    {
+        <PerfectTree<U, Q, D> as Load>::load(&self, zone)
+    }
+}
+
 impl<T, P: Ptr, D: Digest> Load for PerfectTree<T, P, D>
 where T: Load
 {
@@ -705,8 +1639,12 @@ where T: 'static,
 {
     type DecodeBytesError = DecodePerfectTreeDynBytesError<<raw::Node<T, P, D> as Blob>::DecodeBytesError>;
 
-    fn try_size(_height: Self::Metadata) -> Result<usize, !> {
-        Ok(<raw::Node<T, P, D> as Blob>::SIZE)
+    fn try_size(height: Self::Metadata) -> Result<usize, HeightError> {
+        if height <= Height::MAX {
+            Ok(<raw::Node<T, P, D> as Blob>::SIZE)
+        } else {
+            Err(HeightError)
+        }
     }
 
     fn encode_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
@@ -797,8 +1735,12 @@ where T: 'static,
 {
     type DecodeBytesError = DecodeTipDynBytesError<<raw::Node<T, P, D> as Blob>::DecodeBytesError>;
 
-    fn try_size(_height: Self::Metadata) -> Result<usize, !> {
-        Ok(<raw::Node<T, P, D> as Blob>::SIZE)
+    fn try_size(height: Self::Metadata) -> Result<usize, HeightError> {
+        if height <= Height::MAX {
+            Ok(<raw::Node<T, P, D> as Blob>::SIZE)
+        } else {
+            Err(HeightError)
+        }
     }
 
     fn encode_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
@@ -889,8 +1831,12 @@ where T: 'static,
 {
     type DecodeBytesError = DecodePairDynBytesError<<raw::Pair<T, P, D> as Blob>::DecodeBytesError>;
 
-    fn try_size(_height: Self::Metadata) -> Result<usize, !> {
-        Ok(<raw::Pair<T, P, D> as Blob>::SIZE)
+    fn try_size(height: Self::Metadata) -> Result<usize, HeightError> {
+        if height <= Height::MAX {
+            Ok(<raw::Pair<T, P, D> as Blob>::SIZE)
+        } else {
+            Err(HeightError)
+        }
     }
 
     fn encode_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
@@ -943,9 +1889,35 @@ impl<T, P: Ptr, D: Digest> Drop for PerfectTree<T, P, D> {
 
 impl<T, P: Ptr, D: Digest> Drop for TipDyn<T, P, D> {
     fn drop(&mut self) {
+        // Dropping a pair in place would drop both halves, recursing once per level. Instead
+        // dirty pairs are taken out of their pointers and split up with an explicit stack, so the
+        // call depth doesn't grow with the height of the tree.
         let height = self.height();
+        let ptr = unsafe { ptr::read(&self.raw.ptr) };
+
+        let mut stack: Vec<Pair<T, P, D>> = Self::take_dirty_pair(ptr, height).into_iter().collect();
+        while let Some(pair) = stack.pop() {
+            let (left, right) = pair.into_split();
+            for half in vec![left, right] {
+                if let Kind::Tip(tip) = half.into_kind() {
+                    let height = tip.height();
+                    let (_, ptr) = tip.into_raw_node().into_raw_parts();
+                    stack.extend(Self::take_dirty_pair(ptr, height));
+                }
+            }
+        }
+    }
+}
+
+impl<T, P: Ptr, D: Digest> TipDyn<T, P, D> {
+    /// Takes the pair out of a dirty pointer, without dropping its halves.
+    ///
+    /// Clean pointers don't own their pair, so there's nothing to take or drop.
+    fn take_dirty_pair(ptr: P, height: NonZeroHeight) -> Option<Pair<T, P, D>> {
         unsafe {
-            self.raw.ptr.dealloc::<PairDyn<T, P, D>>(height);
+            ptr.try_take_dirty::<PairDyn<T, P, D>>(height)
+               .ok()
+               .map(MaybeValid::trust)
         }
     }
 }
@@ -958,6 +1930,7 @@ impl<T, P: Ptr, D: Digest> Drop for Tip<T, P, D> {
 
 impl<T, P: Ptr, D: Digest> Drop for PairDyn<T, P, D> {
     fn drop(&mut self) {
+        // Tips drop their own subtrees iteratively, so this doesn't recurse past one level.
         unsafe {
             ptr::drop_in_place(self.left_mut());
             ptr::drop_in_place(self.right_mut());
@@ -1047,6 +2020,96 @@ where T: fmt::Debug, P: fmt::Debug, D: fmt::Debug,
     }
 }
 
+// --------- cmp/hash impls -----------
+
+impl<T, P: Ptr, Q: Ptr, D: Digest> PartialEq<PerfectTreeDyn<T, Q, D>> for PerfectTreeDyn<T, P, D>
+where T: Commit
+{
+    /// Compares trees by commitment, so trees stored in different zones can be compared.
+    ///
+    /// Cached digests are used when both sides have them, avoiding any re-hashing.
+    fn eq(&self, other: &PerfectTreeDyn<T, Q, D>) -> bool {
+        if self.height() != other.height() {
+            false
+        } else if let (Some(lhs), Some(rhs)) = (self.try_node_digest(), other.try_node_digest()) {
+            lhs == rhs
+        } else {
+            self.node_digest() == other.node_digest()
+        }
+    }
+}
+
+impl<T, P: Ptr, D: Digest> Eq for PerfectTreeDyn<T, P, D>
+where T: Commit
+{}
+
+impl<T, P: Ptr, Q: Ptr, D: Digest> PartialEq<PerfectTree<T, Q, D>> for PerfectTree<T, P, D>
+where T: Commit
+{
+    fn eq(&self, other: &PerfectTree<T, Q, D>) -> bool {
+        self.deref() == other.deref()
+    }
+}
+
+impl<T, P: Ptr, D: Digest> Eq for PerfectTree<T, P, D>
+where T: Commit
+{}
+
+impl<T, P: Ptr, Q: Ptr, D: Digest> PartialOrd<PerfectTreeDyn<T, Q, D>> for PerfectTreeDyn<T, P, D>
+where T: Commit
+{
+    /// Orders trees by length, then by the bytes of their root digests.
+    ///
+    /// Like `eq`, the order depends only on the commitment, not on the zone or pointers.
+    fn partial_cmp(&self, other: &PerfectTreeDyn<T, Q, D>) -> Option<cmp::Ordering> {
+        Some(self.height().cmp(&other.height())
+                 .then_with(|| self.node_digest().as_ref().cmp(other.node_digest().as_ref())))
+    }
+}
+
+impl<T, P: Ptr, D: Digest> Ord for PerfectTreeDyn<T, P, D>
+where T: Commit
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.partial_cmp(other).expect("trees are totally ordered")
+    }
+}
+
+impl<T, P: Ptr, Q: Ptr, D: Digest> PartialOrd<PerfectTree<T, Q, D>> for PerfectTree<T, P, D>
+where T: Commit
+{
+    fn partial_cmp(&self, other: &PerfectTree<T, Q, D>) -> Option<cmp::Ordering> {
+        self.deref().partial_cmp(other.deref())
+    }
+}
+
+impl<T, P: Ptr, D: Digest> Ord for PerfectTree<T, P, D>
+where T: Commit
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.deref().cmp(other.deref())
+    }
+}
+
+impl<T, P: Ptr, D: Digest> hash::Hash for PerfectTreeDyn<T, P, D>
+where T: Commit,
+      D: hash::Hash,
+{
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.height().hash(state);
+        self.node_digest().hash(state);
+    }
+}
+
+impl<T, P: Ptr, D: Digest> hash::Hash for PerfectTree<T, P, D>
+where T: Commit,
+      D: hash::Hash,
+{
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.deref().hash(state)
+    }
+}
+
 // --------- commit impls -----------
 
 impl<T, P: Ptr, D: Digest> Commit for PairDyn<T, P, D>
@@ -1056,7 +2119,7 @@ where T: Commit,
 
     fn to_commitment(&self) -> Self::Commitment {
         let left = self.left().to_commitment();
-        let right = self.left().to_commitment();
+        let right = self.right().to_commitment();
 
         Pair::try_join(left, right).ok().unwrap()
     }
@@ -1209,10 +2272,8 @@ impl<Q: PtrBlob, T: Save<Q>, P: Ptr, D: Digest> TipDynSavePoll<Q, T, P, D> {
 
 impl<Q: PtrBlob, T: Save<Q>, P: Ptr, D: Digest> PairDynSavePoll<Q, T, P, D> {
     fn encode_raw_pair_blob(&self) -> raw::Pair<T::DstBlob, Q, D> {
-        raw::Pair {
-            left: self.left.encode_raw_node_blob(),
-            right: self.right.encode_raw_node_blob(),
-        }
+        raw::Pair::new(self.left.encode_raw_node_blob(),
+                       self.right.encode_raw_node_blob())
     }
 }
 
@@ -1237,6 +2298,10 @@ where T: Commit + Save<Q>,
         self.right.save_ref_poll(saver)
     }
 
+    fn ref_blobs_remaining(&self) -> usize {
+        self.left.ref_blobs_remaining() + self.right.ref_blobs_remaining()
+    }
+
     fn encode_blob_dyn_bytes<'a>(&self, dst: BytesUninit<'a, Self::DstBlob>) -> Bytes<'a, Self::DstBlob> {
         dst.write_struct()
            .write_field(&self.encode_raw_pair_blob())
@@ -1269,6 +2334,13 @@ where T: Commit + Save<Q>,
         }
     }
 
+    fn ref_blobs_remaining(&self) -> usize {
+        match self {
+            Self::Leaf(leaf) => leaf.blobs_remaining(),
+            Self::Tip(tip) => tip.ref_blobs_remaining(),
+        }
+    }
+
     fn encode_blob_dyn_bytes<'a>(&self, dst: BytesUninit<'a, Self::DstBlob>) -> Bytes<'a, Self::DstBlob> {
         dst.write_struct()
            .write_field(&self.encode_raw_node_blob())
@@ -1295,6 +2367,7 @@ where T: Commit + Save<Q>,
         loop {
             self.state = match &mut self.state {
                 State::Clean(p_clean) => {
+                    // If the saver already has the pair, we're done without touching the children.
                     match saver.save_ptr::<PairDyn<T, P, D>>(*p_clean, self.height)? {
                         Ok(q_ptr) => State::Done(q_ptr),
                         Err(target_poll) => State::Dirty(target_poll),
@@ -1313,6 +2386,14 @@ where T: Commit + Save<Q>,
         }
     }
 
+    fn ref_blobs_remaining(&self) -> usize {
+        match &self.state {
+            State::Clean(_) => 1,
+            State::Dirty(target) => 1 + target.ref_blobs_remaining(),
+            State::Done(_) => 0,
+        }
+    }
+
     fn encode_blob_dyn_bytes<'a>(&self, dst: BytesUninit<'a, Self::DstBlob>) -> Bytes<'a, Self::DstBlob> {
         dst.write_struct()
            .write_field(&self.encode_raw_node_blob())
@@ -1395,6 +2476,10 @@ where T: Commit + Save<Q>,
             PerfectTree::from_raw_node(raw, height)
         }
     }
+
+    fn blobs_remaining(&self) -> usize {
+        self.0.ref_blobs_remaining()
+    }
 }
 
 impl<Q: PtrBlob, T, P: Ptr, D: Digest> Save<Q> for PerfectTree<T, P, D>
@@ -1419,9 +2504,10 @@ mod tests {
     use hoard::{
         ptr::{
             Heap,
+            PtrClean,
             key::{
-                Map,
-                offset::OffsetSaver,
+                CachingZone, Key, Map, Offset, PileMut, TracingZone,
+                offset::{DedupSaver, DynSaver, OffsetSaver, WriteSaver, save_new},
             },
         },
     };
@@ -1447,12 +2533,122 @@ mod tests {
             1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
             1, 0, 0, 0, 0, 0, 0, 0,
 
-            44, 204, 28, 235, 16, 65, 239, 191, 88, 43, 77, 236, 197, 8, 120, 35, 76, 45, 42, 219, 120, 41, 255, 162, 60, 146, 237, 100, 16, 92, 138, 56,
+            146, 245, 11, 56, 0, 112, 1, 38, 17, 4, 15, 207, 40, 126, 207, 148, 51, 140, 180, 214, 185, 96, 155, 19, 181, 40, 39, 191, 210, 34, 244, 149,
             2, 0, 0, 0, 0, 0, 0, 0,
             1 // height
         ]);
     }
 
+    /// Saver that only allows `budget` blobs to be saved before failing.
+    struct StepSaver<P> {
+        marker: PhantomData<fn() -> P>,
+        dst: Vec<u8>,
+        budget: usize,
+    }
+
+    impl<P: PtrClean> Saver for StepSaver<P> {
+        type Error = ();
+        type SrcPtr = P;
+        type DstPtr = Offset;
+
+        fn save_ptr<T: ?Sized>(&mut self, _ptr: P, _metadata: T::Metadata)
+            -> Result<Result<Offset, T::SaveRefPoll>, ()>
+            where T: SaveRef<Offset>,
+                  P::Zone: AsZone<T::Zone>,
+        {
+            unreachable!("only dirty values are saved")
+        }
+
+        fn poll<T>(&mut self, poll: &mut T) -> Result<(), ()>
+            where T: SaveRefPoll<DstPtr = Offset>,
+                  P: From<T::SrcPtr>,
+                  P::Zone: AsZone<<T::SrcPtr as Ptr>::Zone>,
+        {
+            let mut inner = StepSaver::<T::SrcPtr> {
+                marker: PhantomData,
+                dst: mem::take(&mut self.dst),
+                budget: self.budget,
+            };
+            let r = poll.save_ref_poll(&mut inner);
+            self.dst = inner.dst;
+            self.budget = inner.budget;
+            r
+        }
+
+        fn poll_ref<T>(&mut self, poll: &mut T) -> Result<Offset, ()>
+            where T: SaveRefPoll<DstPtr = Offset>,
+                  P: From<T::SrcPtr>,
+                  P::Zone: AsZone<<T::SrcPtr as Ptr>::Zone>,
+        {
+            self.poll(poll)?;
+            self.save_blob_with(poll.blob_metadata(), |dst| poll.encode_blob_dyn_bytes(dst))
+        }
+
+        fn save_blob_with<T: ?Sized, F>(&mut self, metadata: T::Metadata, f: F) -> Result<Offset, ()>
+            where T: BlobDyn,
+                  F: for<'a> FnOnce(BytesUninit<'a, T>) -> Bytes<'a, T>
+        {
+            self.budget = self.budget.checked_sub(1).ok_or(())?;
+
+            let size = T::try_size(metadata).expect("valid metadata");
+            let old_len = self.dst.len();
+            self.dst.resize(old_len + size, 0);
+
+            let dst = BytesUninit::<T>::from_bytes(&mut self.dst[old_len ..], metadata).expect("valid metadata");
+            f(dst);
+            Ok(Offset::new(old_len as u64))
+        }
+    }
+
+    #[test]
+    fn save_blobs_remaining() {
+        let tree = tree4();
+
+        let mut poll = Save::<Offset>::init_save(&tree);
+        let mut saver = StepSaver::<!> { marker: PhantomData, dst: vec![], budget: 0 };
+
+        // Four leaf values plus three pairs
+        let mut remaining = SavePoll::blobs_remaining(&poll);
+        assert_eq!(remaining, 7);
+
+        loop {
+            saver.budget = 1;
+            let r = poll.save_poll(&mut saver);
+
+            let new_remaining = SavePoll::blobs_remaining(&poll);
+            assert!(new_remaining < remaining);
+            remaining = new_remaining;
+
+            if r.is_ok() {
+                break;
+            }
+        }
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn pair_bag_accessors() {
+        let leaf0 = PerfectTree::<u8, Heap>::new_leaf(0u8);
+        let leaf1 = PerfectTree::<u8, Heap>::new_leaf(1u8);
+        let pair = Pair::try_join(leaf0, leaf1).unwrap();
+
+        let bag: Bag<PairDyn<u8, Heap>, Heap> = Heap::alloc(pair);
+        assert_eq!(bag.metadata(), 1u8);
+
+        let pair = bag.try_get_dirty().into_ok();
+        assert_eq!(pair.height(), 1u8);
+        assert_eq!(pair.left().get(0).unwrap(), &0);
+        assert_eq!(pair.right().get(0).unwrap(), &1);
+
+        let via_ptr = unsafe { bag.ptr().try_get_dirty::<PairDyn<u8, Heap>>(bag.metadata()) }
+                                        .into_ok().trust();
+        assert!(ptr::eq(pair, via_ptr));
+
+        let tip = Tip::new_unchecked(None, bag);
+        assert_eq!(tip.height(), 1u8);
+        assert_eq!(tip.get_leaf(1).unwrap().get(), &1);
+    }
+
     #[test]
     fn test_get() {
         let leaf0 = PerfectTree::<u8, Heap>::new_leaf(0u8);
@@ -1464,6 +2660,1391 @@ mod tests {
         assert_eq!(tree0.get(usize::MAX), None);
     }
 
+    #[test]
+    fn get_pair_verified() {
+        let leaf0 = PerfectTree::<u8, Heap>::new_leaf(0u8);
+        let leaf1 = PerfectTree::<u8, Heap>::new_leaf(1u8);
+        let tree0 = PerfectTree::try_join(leaf0, leaf1).unwrap();
+
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, mut buf) = saver.try_save(&tree0).unwrap();
+
+        {
+            let bag = load(&buf, offset);
+            let tree = bag.get();
+            match tree.kind() {
+                Kind::Tip(tip) => {
+                    let pair = tip.get_pair_verified().unwrap();
+                    assert_eq!(pair.left().get(0).unwrap(), &0);
+                },
+                Kind::Leaf(_) => panic!(),
+            }
+        }
+
+        // Corrupt the first byte of the tip digest
+        buf[offset.get() as usize] ^= 1;
+
+        let bag = load(&buf, offset);
+        let tree = bag.get();
+        match tree.kind() {
+            Kind::Tip(tip) => {
+                let err = tip.get_pair_verified().unwrap_err();
+                assert_ne!(err.expected, err.actual);
+                assert_eq!(err.expected.as_ref()[0] ^ 1, err.actual.as_ref()[0]);
+            },
+            Kind::Leaf(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn load_verified() {
+        let leaf0 = PerfectTree::<u8, Heap>::new_leaf(0u8);
+        let leaf1 = PerfectTree::<u8, Heap>::new_leaf(1u8);
+        let tree0 = PerfectTree::try_join(leaf0, leaf1).unwrap();
+
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, mut buf) = saver.try_save(&tree0).unwrap();
+        let height = NonZeroHeight::MIN;
+
+        {
+            let map: &[u8] = &buf;
+            let tip = map.get_blob_with::<TipDyn<u8, Offset>, _, _>(offset, height, |bytes| {
+                TipDyn::<u8, Key<[u8]>>::load_verified(bytes, &map)
+            }).unwrap().unwrap();
+            assert_eq!(tip.get_pair().left().get(0).unwrap(), &0);
+        }
+
+        // Flip a byte of the stored digest
+        buf[offset.get() as usize] ^= 1;
+
+        let map: &[u8] = &buf;
+        let r = map.get_blob_with::<TipDyn<u8, Offset>, _, _>(offset, height, |bytes| {
+            TipDyn::<u8, Key<[u8]>>::load_verified(bytes, &map)
+        }).unwrap();
+        match r {
+            Err(LoadVerifiedError::Mismatch(err)) => assert_ne!(err.expected, err.actual),
+            _ => panic!("corrupt digest accepted"),
+        }
+    }
+
+    #[test]
+    fn load_verified_checks_right_child() {
+        let leaf0 = PerfectTree::<u8, Heap>::new_leaf(0u8);
+        let leaf1 = PerfectTree::<u8, Heap>::new_leaf(1u8);
+        let tree0 = PerfectTree::try_join(leaf0, leaf1).unwrap();
+
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, mut buf) = saver.try_save(&tree0).unwrap();
+        let height = NonZeroHeight::MIN;
+
+        // Flip a byte of the right leaf's digest, stored after the two values and the left leaf
+        buf[2 + 40] ^= 1;
+
+        let map: &[u8] = &buf;
+        let r = map.get_blob_with::<TipDyn<u8, Offset>, _, _>(offset, height, |bytes| {
+            TipDyn::<u8, Key<[u8]>>::load_verified(bytes, &map)
+        }).unwrap();
+        match r {
+            Err(LoadVerifiedError::Mismatch(err)) => assert_ne!(err.expected, err.actual),
+            _ => panic!("corrupt right child accepted"),
+        }
+    }
+
+    #[test]
+    fn hash_by_node_digest() {
+        use std::collections::HashSet;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let new_tree = |a, b| {
+            let leaf0 = PerfectTree::<u8, Heap>::new_leaf(a);
+            let leaf1 = PerfectTree::<u8, Heap>::new_leaf(b);
+            PerfectTree::try_join(leaf0, leaf1).unwrap()
+        };
+
+        let mut set = HashSet::new();
+        set.insert(new_tree(0, 1));
+        set.insert(new_tree(0, 1));
+        assert_eq!(set.len(), 1);
+
+        set.insert(new_tree(2, 1));
+        assert_eq!(set.len(), 2);
+
+        let tree0 = new_tree(0, 1);
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree0).unwrap();
+
+        let bag = load(&buf, offset);
+        let loaded = bag.get();
+
+        assert!(*loaded == tree0);
+        assert_eq!(hash_of(&*loaded), hash_of(&tree0));
+        assert_eq!(hash_of(&*loaded), hash_of(set.get(&tree0).unwrap()));
+    }
+
+    #[test]
+    fn ord() {
+        let leaf = |n| PerfectTree::<u8, Heap>::new_leaf(n);
+        let pair = |a, b| PerfectTree::try_join(leaf(a), leaf(b)).unwrap();
+
+        // Shorter trees sort first, regardless of digest
+        for &(a, b) in [(0, 1), (1, 0), (42, 42)].iter() {
+            for n in 0 .. 4 {
+                assert!(leaf(n) < pair(a, b));
+                assert!(pair(a, b) > leaf(n));
+            }
+        }
+
+        // Equal length trees are ordered by digest
+        let (x, y) = (pair(0, 1), pair(1, 0));
+        let expected = x.node_digest().as_ref().cmp(y.node_digest().as_ref());
+        assert_ne!(expected, cmp::Ordering::Equal);
+        assert_eq!(x.cmp(&y), expected);
+        assert_eq!(y.cmp(&x), expected.reverse());
+        assert_eq!(x.cmp(&pair(0, 1)), cmp::Ordering::Equal);
+
+        let mut trees = vec![pair(1, 0), leaf(3), pair(0, 1), leaf(2)];
+        trees.sort();
+        assert_eq!(trees[0].height(), 0);
+        assert_eq!(trees[1].height(), 0);
+        assert!(trees[0].node_digest().as_ref() < trees[1].node_digest().as_ref());
+        assert!(trees[2].node_digest().as_ref() < trees[3].node_digest().as_ref());
+    }
+
+    #[test]
+    fn eq_uses_cached_digests() {
+        use std::cell::Cell;
+
+        thread_local! {
+            static COMMIT_COUNT: Cell<usize> = Cell::new(0);
+        }
+
+        #[derive(Debug)]
+        struct Counted(u8);
+
+        impl Commit for Counted {
+            type Commitment = u8;
+
+            fn to_commitment(&self) -> u8 {
+                COMMIT_COUNT.with(|count| count.set(count.get() + 1));
+                self.0
+            }
+        }
+
+        let new_tree = || {
+            let leaf0 = PerfectTree::<Counted, Heap>::new_leaf(Counted(0));
+            let leaf1 = PerfectTree::<Counted, Heap>::new_leaf(Counted(1));
+            PerfectTree::try_join(leaf0, leaf1).unwrap()
+        };
+        let commit_count = || COMMIT_COUNT.with(Cell::get);
+
+        let tree_a = new_tree();
+        let tree_b = new_tree();
+        assert!(tree_a.try_node_digest().is_none());
+
+        let _ = tree_a.node_digest();
+        let _ = tree_b.node_digest();
+        assert_eq!(commit_count(), 4);
+
+        // Both roots cached, so no leaves should be committed
+        assert!(tree_a == tree_b);
+        assert_eq!(commit_count(), 4);
+
+        // A fresh tree has to be hashed
+        let tree_c = new_tree();
+        assert!(tree_a == tree_c);
+        assert_eq!(commit_count(), 6);
+    }
+
+    #[test]
+    fn node_digest_rfc6962() {
+        use hex_literal::hex;
+        use crate::commit::Rfc6962;
+
+        let tree = PerfectTree::<u8, Heap>::try_join(PerfectTree::new_leaf(0x00),
+                                                     PerfectTree::new_leaf(0x10)).unwrap();
+        let root = tree.node_digest_with::<Rfc6962>();
+        assert_eq!(root.as_bytes(),
+                   &hex!("e8bba54899f34c767fa1b827f136cb9fde1e3b15ff9a0a57781fc0832e523548"));
+
+        let leaf0: Sha256Digest = Rfc6962::hash_leaf(&0x00u8);
+        let leaf1: Sha256Digest = Rfc6962::hash_leaf(&0x10u8);
+        assert_eq!(root, Rfc6962::hash_node(&leaf0, &leaf1));
+
+        // The default scheme is unchanged
+        assert_ne!(root, tree.node_digest());
+    }
+
+    #[test]
+    fn node_digest_in_domain() {
+        struct Foo;
+        impl CommitContext for Foo {
+            const DOMAIN: &'static [u8] = b"foo";
+        }
+
+        struct Bar;
+        impl CommitContext for Bar {
+            const DOMAIN: &'static [u8] = b"bar";
+        }
+
+        let leaf0 = PerfectTree::<u8, Heap>::new_leaf(0u8);
+        let leaf1 = PerfectTree::<u8, Heap>::new_leaf(1u8);
+        let tree = PerfectTree::try_join(leaf0, leaf1).unwrap();
+
+        assert_eq!(tree.node_digest_in::<()>(), tree.node_digest());
+
+        let foo = tree.node_digest_in::<Foo>();
+        let bar = tree.node_digest_in::<Bar>();
+        assert_ne!(foo, tree.node_digest());
+        assert_ne!(foo, bar);
+        assert_eq!(foo, tree.node_digest_in::<Foo>());
+    }
+
+    #[test]
+    fn new_in_pile_mut() {
+        let mut pile: &[u8] = &[];
+
+        let leaf0 = PerfectTree::new_leaf_in(0u8, &mut pile);
+        let leaf1 = PerfectTree::new_leaf_in(1u8, &mut pile);
+        let tree: PerfectTree<u8, PileMut<[u8]>> = PerfectTree::try_join_in(leaf0, leaf1, &mut pile).unwrap();
+
+        assert_eq!(tree.height(), 1);
+        assert_eq!(*tree.get(0).unwrap(), 0);
+        assert_eq!(*tree.get(1).unwrap(), 1);
+
+        let heap_tree = PerfectTree::<u8, Heap>::try_join(PerfectTree::new_leaf(0u8),
+                                                          PerfectTree::new_leaf(1u8)).unwrap();
+        assert_eq!(tree.node_digest(), heap_tree.node_digest());
+    }
+
+    #[test]
+    fn set_leaf_pile_mut() {
+        let mut pile: &[u8] = &[];
+
+        let leaves: Vec<_> = (0u8 .. 4).map(|i| PerfectTree::new_leaf_in(i, &mut pile)).collect();
+        let mut leaves = leaves.into_iter();
+        let mut next = || leaves.next().unwrap();
+        let left = PerfectTree::try_join_in(next(), next(), &mut pile).unwrap();
+        let right = PerfectTree::try_join_in(next(), next(), &mut pile).unwrap();
+        let mut tree: PerfectTree<u8, PileMut<[u8]>> = PerfectTree::try_join_in(left, right, &mut pile).unwrap();
+        let _ = tree.node_digest();
+
+        assert_eq!(tree.set_leaf(2, 42), Ok(2));
+        assert_eq!(tree.set_leaf(4, 43), Err(43));
+        assert!(tree.try_node_digest().is_none());
+
+        *tree.get_leaf_mut(0).unwrap().get_mut() += 10;
+        assert!(tree.get_leaf_mut(4).is_none());
+
+        let values: Vec<u8> = (0 .. 4).map(|i| *tree.get(i).unwrap()).collect();
+        assert_eq!(values, &[10, 1, 42, 3]);
+
+        let heap_tree = PerfectTree::<u8, Heap>::try_join(
+            PerfectTree::try_join(PerfectTree::new_leaf(10), PerfectTree::new_leaf(1)).unwrap(),
+            PerfectTree::try_join(PerfectTree::new_leaf(42), PerfectTree::new_leaf(3)).unwrap(),
+        ).unwrap();
+        assert_eq!(tree.node_digest(), heap_tree.node_digest());
+
+        // Clean leaves are loaded from the pile when mutated
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let mut bag = load_mut(&buf, offset);
+        let loaded = bag.get_mut();
+
+        assert_eq!(loaded.set_leaf(1, 11), Ok(1));
+        assert_eq!(*loaded.get(0).unwrap(), 10);
+        assert_eq!(*loaded.get(1).unwrap(), 11);
+        assert_ne!(loaded.node_digest(), heap_tree.node_digest());
+    }
+
+    #[test]
+    fn contents_eq() {
+        let heap_tree = |values: [u8; 2]| {
+            PerfectTree::<u8, Heap>::try_join(PerfectTree::new_leaf(values[0]),
+                                              PerfectTree::new_leaf(values[1])).unwrap()
+        };
+
+        let mut pile: &[u8] = &[];
+        let leaf0 = PerfectTree::new_leaf_in(1u8, &mut pile);
+        let leaf1 = PerfectTree::new_leaf_in(2u8, &mut pile);
+        let pile_tree: PerfectTree<u8, PileMut<[u8]>> = PerfectTree::try_join_in(leaf0, leaf1, &mut pile).unwrap();
+
+        assert!(heap_tree([1, 2]).contents_eq(&heap_tree([1, 2])));
+        assert!(heap_tree([1, 2]).contents_eq(&pile_tree));
+        assert!(pile_tree.contents_eq(&heap_tree([1, 2])));
+
+        assert!(!heap_tree([1, 2]).contents_eq(&heap_tree([2, 1])));
+        assert!(!heap_tree([1, 2]).contents_eq(&heap_tree([1, 3])));
+        assert!(!pile_tree.contents_eq(&heap_tree([0, 2])));
+
+        // Digests are never calculated
+        let tree = heap_tree([1, 2]);
+        assert!(tree.contents_eq(&heap_tree([1, 2])));
+        assert!(tree.try_node_digest().is_none());
+
+        let leaf = PerfectTree::<u8, Heap>::new_leaf(1u8);
+        assert!(!leaf.contents_eq(&tree));
+        assert!(!tree.contents_eq(&leaf));
+    }
+
+    #[test]
+    fn fold() {
+        let values = [3u8, 1, 4, 1, 5, 9, 2, 6];
+
+        let mut trees: Vec<_> = values.iter().map(|n| PerfectTree::<u8, Heap>::new_leaf(*n)).collect();
+        while trees.len() > 1 {
+            let mut pairs = trees.into_iter();
+            trees = vec![];
+            while let (Some(left), Some(right)) = (pairs.next(), pairs.next()) {
+                trees.push(PerfectTree::try_join(left, right).unwrap());
+            }
+        }
+        let tree = trees.pop().unwrap();
+
+        let sum = tree.fold(0u32, |sum, leaf| sum + *leaf.get() as u32);
+        assert_eq!(sum, values.iter().map(|n| *n as u32).sum());
+
+        let in_order = tree.fold(vec![], |mut v, leaf| { v.push(*leaf.get()); v });
+        assert_eq!(in_order, values);
+
+        let leaf = PerfectTree::<u8, Heap>::new_leaf(42);
+        assert_eq!(leaf.fold(0u32, |sum, leaf| sum + *leaf.get() as u32), 42);
+    }
+
+    #[test]
+    fn assert_len() {
+        let leaf = PerfectTree::<u8, Heap>::new_leaf(0);
+        assert!(leaf.assert_len(1).is_ok());
+        assert_eq!(leaf.assert_len(0).unwrap_err(),
+                   LenMismatch { expected: 0, found: 1 });
+
+        let tree = PerfectTree::try_join(PerfectTree::<u8, Heap>::new_leaf(0),
+                                         PerfectTree::new_leaf(1)).unwrap();
+        assert_eq!(tree.assert_len(2).unwrap().height(), 1);
+        assert_eq!(tree.assert_len(3).unwrap_err(),
+                   LenMismatch { expected: 3, found: 2 });
+        assert_eq!(tree.assert_len(3).unwrap_err().to_string(),
+                   "expected 3 items, found 2");
+    }
+
+    #[test]
+    fn get_traced() {
+        let tree = tree4();
+
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let zone = TracingZone::new(&buf[..]);
+        let zone_ref = &zone;
+        let key = Key::<TracingZone<&[u8]>>::from_blob(offset, &zone_ref);
+        let bag: Bag<PerfectTree<u8, Key<TracingZone<&[u8]>>>, _> = unsafe { Bag::from_raw_parts(key, ()) };
+        let loaded = bag.get();
+        assert_eq!(zone.take_trace(), vec![offset]);
+
+        for i in 0 .. 4 {
+            assert_eq!(*loaded.get(i).unwrap(), i as u8);
+
+            // Two pairs, then the leaf value
+            let trace = zone.take_trace();
+            assert_eq!(trace.len(), usize::from(loaded.height().get()) + 1);
+            assert!(trace.windows(2).all(|w| w[0].get() > w[1].get()));
+        }
+    }
+
+    #[test]
+    fn set_leaf_rehashes_path() {
+        use std::cell::Cell;
+        use std::convert::TryFrom;
+        use crate::commit::{Hasher, sha256::Sha256Hasher};
+
+        thread_local! {
+            static HASH_COUNT: Cell<usize> = Cell::new(0);
+        }
+
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+        struct CountedDigest([u8; 32]);
+
+        impl AsRef<[u8]> for CountedDigest {
+            fn as_ref(&self) -> &[u8] { &self.0 }
+        }
+
+        impl AsMut<[u8]> for CountedDigest {
+            fn as_mut(&mut self) -> &mut [u8] { &mut self.0 }
+        }
+
+        impl Primitive for CountedDigest {
+            const BLOB_SIZE: usize = 32;
+            type DecodeBytesError = !;
+
+            fn encode_blob_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
+                dst.write_bytes(&self.0)
+            }
+
+            fn decode_blob_bytes(blob: Bytes<'_, Self>) -> Result<Self, !> {
+                Ok(Self(TryFrom::try_from(&blob[..]).unwrap()))
+            }
+        }
+
+        #[derive(Default)]
+        struct CountedHasher(Sha256Hasher);
+
+        impl Hasher for CountedHasher {
+            type Output = CountedDigest;
+
+            fn hash_bytes(&mut self, buf: &[u8]) {
+                self.0.hash_bytes(buf)
+            }
+
+            fn finish(self) -> CountedDigest {
+                HASH_COUNT.with(|count| count.set(count.get() + 1));
+                CountedDigest(self.0.finish().into())
+            }
+        }
+
+        impl Digest for CountedDigest {
+            type Hasher = CountedHasher;
+        }
+
+        let hash_count = || HASH_COUNT.with(|count| count.replace(0));
+
+        for height in 1 .. 8 {
+            // u64 leaves are committed verbatim, so only pairs are hashed
+            let items = (0 .. 1u64 << height).collect();
+            let (mut tree, _) = PerfectTree::<u64, Heap, CountedDigest>::try_from_padded(items, || 0).unwrap();
+            let _ = tree.node_digest();
+            assert_eq!(hash_count(), (1 << height) - 1);
+
+            assert_eq!(tree.set_leaf(1, 42), Ok(1));
+            let _ = tree.node_digest();
+            assert_eq!(hash_count(), height);
+
+            // Nothing changed, so the cached root is reused
+            let _ = tree.node_digest();
+            assert_eq!(hash_count(), 0);
+        }
+    }
+
+    #[test]
+    fn verbatim_len() {
+        #[derive(Default)]
+        struct CountingHasher(usize);
+
+        impl crate::commit::Hasher for CountingHasher {
+            type Output = usize;
+
+            fn hash_bytes(&mut self, buf: &[u8]) {
+                self.0 += buf.len();
+            }
+
+            fn finish(self) -> usize {
+                self.0
+            }
+        }
+
+        #[track_caller]
+        fn t<T: Commit>(value: &T) {
+            assert_eq!(value.hash_commitment_with(CountingHasher::default()), T::VERBATIM_LEN);
+
+            let mut buf = vec![];
+            value.write_verbatim(&mut buf).unwrap();
+            assert_eq!(buf.len(), T::VERBATIM_LEN);
+        }
+
+        let tip = Tip::<u8, Heap>::try_join(PerfectTree::new_leaf(0), PerfectTree::new_leaf(1)).unwrap();
+        let pair = Pair::<u8, Heap>::try_join(PerfectTree::new_leaf(0), PerfectTree::new_leaf(1)).unwrap();
+
+        t(&0u8);
+        t(&Leaf::<u8, Heap>::new(0));
+        t(&pair);
+        t(&tip);
+        t(&PerfectTree::from(tip));
+
+        assert_eq!(<Leaf<u8, Heap> as Commit>::VERBATIM_LEN, 32);
+        assert_eq!(<Pair<u8, Heap> as Commit>::VERBATIM_LEN, <raw::Pair<u8, ()> as Blob>::SIZE + 1);
+    }
+
+    #[test]
+    fn commit_from_refs() {
+        for n in 0 .. 4 {
+            let values: Vec<u8> = (0 .. (1 << n)).collect();
+            let refs: Vec<&u8> = values.iter().collect();
+
+            let mut trees: Vec<_> = values.iter().map(|v| PerfectTree::<u8, Heap>::new_leaf(*v)).collect();
+            while trees.len() > 1 {
+                let mut pairs = trees.into_iter();
+                trees = vec![];
+                while let (Some(left), Some(right)) = (pairs.next(), pairs.next()) {
+                    trees.push(PerfectTree::try_join(left, right).unwrap());
+                }
+            }
+            let tree = trees.pop().unwrap();
+
+            let commitment: Commitment<u8> = PerfectTree::commit_from_refs(&refs).unwrap();
+            assert_eq!(commitment.height(), tree.height());
+            assert_eq!(commitment.try_node_digest(), Some(tree.node_digest()));
+            assert_eq!(commitment.try_node_digest(), tree.to_commitment().try_node_digest());
+        }
+
+        assert!(PerfectTree::<u8, (), Sha256Digest>::commit_from_refs(&[]).is_none());
+        assert!(PerfectTree::<u8, (), Sha256Digest>::commit_from_refs(&[&1, &2, &3]).is_none());
+    }
+
+    #[test]
+    fn verify_root() {
+        let tree = tree4();
+
+        let values = [0u8, 1, 2, 3];
+        let refs: Vec<&u8> = values.iter().collect();
+        let expected = PerfectTree::commit_from_refs(&refs).unwrap().node_digest();
+
+        assert!(tree.try_node_digest().is_none());
+        assert!(tree.verify_root(&expected));
+        assert_eq!(tree.try_node_digest(), Some(expected));
+
+        let mut wrong = expected;
+        wrong.as_mut()[31] ^= 1;
+        assert!(!tree.verify_root(&wrong));
+        assert!(!tree.verify_root(&Sha256Digest::default()));
+    }
+
+    #[test]
+    fn draw() {
+        let tree = tree4();
+
+        let drawing = tree.draw();
+        let lines: Vec<&str> = drawing.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("tip 2 "));
+        assert!(lines[1].starts_with("  tip 1 "));
+        assert!(lines[2].starts_with("    leaf 0 "));
+        assert!(lines[6].starts_with("    leaf 3 "));
+
+        let leaf = PerfectTree::<u8, Heap>::new_leaf(42);
+        assert_eq!(leaf.draw().lines().count(), 1);
+    }
+
+    #[test]
+    fn append() {
+        let tree = PerfectTree::<u8, Heap>::new_leaf(0)
+                       .append(PerfectTree::new_leaf(1)).unwrap();
+        let tree = tree.append(PerfectTree::try_join(PerfectTree::new_leaf(2),
+                                                     PerfectTree::new_leaf(3)).unwrap())
+                       .unwrap();
+        assert_eq!(tree.height(), 2);
+        for i in 0 .. 4 {
+            assert_eq!(*tree.get(i).unwrap(), i as u8);
+        }
+
+        let (tree, leaf) = tree.append(PerfectTree::new_leaf(4)).unwrap_err();
+        assert_eq!(tree.height(), 2);
+        assert_eq!(*leaf.get(0).unwrap(), 4);
+    }
+
+    #[test]
+    fn resave_clean_tree() {
+        let tree = tree4();
+
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let bag = load(&buf, offset);
+        let loaded = bag.get();
+
+        // Only the root node itself is written
+        let saver = OffsetSaver::with_base(&buf[..]);
+        let (new_offset, new) = saver.try_save(&*loaded).unwrap();
+        assert_eq!(new_offset, buf.len() as u64);
+        assert_eq!(new.len(), <PerfectTree<u8, Offset> as Blob>::SIZE);
+        assert_eq!(&new[..], &buf[offset.get() as usize ..]);
+    }
+
+    #[test]
+    fn blob_range() {
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![1, 2], || unreachable!()).unwrap();
+        assert_eq!(tree.blob_range(), None);
+
+        // Saved after unrelated bytes
+        let mut pile = vec![0xff; 3];
+        let offset = save_new(&mut pile, &tree).unwrap();
+        let tree_size = <PerfectTree<u8, Offset> as Blob>::SIZE;
+        assert_eq!(offset, (pile.len() - tree_size) as u64);
+
+        let bag = load(&pile, offset);
+        let loaded = bag.get();
+        assert_eq!(loaded.blob_range(), Some(3 .. pile.len() - tree_size));
+
+        let pair = match loaded.kind() {
+            Kind::Tip(tip) => tip.get_pair(),
+            Kind::Leaf(_) => unreachable!(),
+        };
+        assert_eq!(pair.left().blob_range(), Some(3 .. 4));
+        assert_eq!(pair.right().blob_range(), Some(4 .. 5));
+    }
+
+    #[test]
+    fn serialized_size() {
+        for_each_height(|_height, tree| {
+            let (_offset, buf) = OffsetSaver::new(&[][..]).try_save(&tree).unwrap();
+            assert_eq!(tree.serialized_size(), buf.len());
+        });
+    }
+
+    #[test]
+    fn save_new_tree() {
+        let tree = tree4();
+
+        let mut pile = vec![0xff];
+        let offset = save_new(&mut pile, &tree).unwrap();
+        assert_eq!(pile[0], 0xff);
+        assert_eq!(offset, (pile.len() - <PerfectTree<u8, Offset> as Blob>::SIZE) as u64);
+
+        let bag = load(&pile, offset);
+        let loaded = bag.get();
+        assert_eq!(loaded.node_digest(), tree.node_digest());
+        for i in 0 .. 4 {
+            assert_eq!(*loaded.get(i).unwrap(), i as u8);
+        }
+    }
+
+    #[test]
+    fn save_dyn_boxed() {
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![0; 8], || 0).unwrap();
+        let (expected_offset, expected) = OffsetSaver::empty().try_save(&tree).unwrap();
+
+        let mut saver: Box<dyn DynSaver> = Box::new(OffsetSaver::empty());
+        assert_eq!(save_dyn(EMPTY_PILE, &mut *saver, &tree).unwrap(), expected_offset);
+
+        let mut written = vec![];
+        let mut saver: Box<dyn DynSaver + '_> = Box::new(WriteSaver::new(&mut written));
+        assert_eq!(save_dyn(EMPTY_PILE, &mut *saver, &tree).unwrap(), expected_offset);
+        drop(saver);
+        assert_eq!(written, expected);
+
+        // Every leaf is the same, so only one value and one pair per height are saved
+        let mut deduped = vec![];
+        let mut saver: Box<dyn DynSaver + '_> = Box::new(DedupSaver::new(WriteSaver::new(&mut deduped)));
+        let offset = save_dyn(EMPTY_PILE, &mut *saver, &tree).unwrap();
+        drop(saver);
+        assert_eq!(deduped.len(), 1 + 3 * <raw::Pair<u8, Offset> as Blob>::SIZE
+                                    + <PerfectTree<u8, Offset> as Blob>::SIZE);
+
+        let bag = load(&deduped, offset);
+        let loaded = bag.get();
+        assert_eq!(loaded.node_digest(), tree.node_digest());
+        for i in 0 .. 8 {
+            assert_eq!(*loaded.get(i).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn map() {
+        let tree = tree4();
+        let tree_digest = tree.node_digest();
+
+        let mapped: PerfectTree<u16, Heap> = tree.map(|n| n as u16 * 1000);
+        assert_eq!(mapped.height(), 2);
+        assert_eq!(mapped.len(), NonZeroLength::new(4).unwrap());
+        for i in 0 .. 4 {
+            assert_eq!(*mapped.get(i).unwrap(), i as u16 * 1000);
+        }
+        assert_ne!(mapped.node_digest(), tree_digest);
+
+        let leaf = PerfectTree::<u8, Heap>::new_leaf(7).map(u16::from);
+        assert_eq!(*leaf.get(0).unwrap(), 7);
+    }
+
+    #[test]
+    fn prune_values() {
+        fn levels<T: Commit + Load>(tree: &PerfectTreeDyn<T, Heap>) -> Vec<Vec<Sha256Digest>>
+            where <Heap as Ptr>::Zone: AsZone<T::Zone>
+        {
+            let height = tree.height().get();
+            (0 ..= height).map(|level| {
+                let level = Height::new(level).unwrap();
+                (0 .. 1 << (height - level.get()))
+                    .map(|i| tree.digest_at(level, i).unwrap())
+                    .collect()
+            }).collect()
+        }
+
+        for_each_height(|height, tree| {
+            let root = tree.root_digest();
+            let expected = levels(&tree);
+
+            let pruned: PerfectTree<(), Heap> = tree.prune_values();
+            assert_eq!(pruned.height(), height);
+            assert_eq!(pruned.root_digest(), root);
+            assert!(pruned.verify_root(&root));
+
+            // Every sibling digest a proof could need is unchanged
+            assert_eq!(levels(&pruned), expected);
+        });
+    }
+
+    #[test]
+    fn is_sorted_by() {
+        for_each_height(|_height, tree| {
+            assert!(tree.is_sorted_by(|a, b| a.cmp(b)));
+        });
+
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![1, 2, 2, 3], || unreachable!()).unwrap();
+        assert!(tree.is_sorted_by(|a, b| a.cmp(b)));
+        assert!(!tree.is_sorted_by(|a, b| b.cmp(a)));
+
+        // Unsorted across the halves of the tree
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![1, 3, 2, 4], || unreachable!()).unwrap();
+        assert!(!tree.is_sorted_by(|a, b| a.cmp(b)));
+
+        let leaf = PerfectTree::<u8, Heap>::new_leaf(42);
+        assert!(leaf.is_sorted_by(|a, b| a.cmp(b)));
+
+        // Unsorted only where the halves of the root meet, or only within a half
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![1, 2, 3, 5, 4, 6, 7, 8], || unreachable!()).unwrap();
+        assert!(!tree.is_sorted_by(|a, b| a.cmp(b)));
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![1, 2, 3, 4, 5, 6, 8, 7], || unreachable!()).unwrap();
+        assert!(!tree.is_sorted_by(|a, b| a.cmp(b)));
+
+        // Every item is compared with the one before it, once, in order
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded((0 .. 16).collect(), || unreachable!()).unwrap();
+        let mut compared = vec![];
+        assert!(tree.is_sorted_by(|a, b| {
+            compared.push((*a, *b));
+            a.cmp(b)
+        }));
+        assert_eq!(compared, (0 .. 15).map(|i| (i, i + 1)).collect::<Vec<_>>());
+
+        // Stops at the first unsorted pair
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![0, 1, 3, 2, 4, 5, 7, 6], || unreachable!()).unwrap();
+        let mut count = 0;
+        assert!(!tree.is_sorted_by(|a, b| {
+            count += 1;
+            a.cmp(b)
+        }));
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn try_from_padded() {
+        let (tree, len) = PerfectTree::<u8, Heap>::try_from_padded(vec![1, 2, 3], || 0).unwrap();
+        assert_eq!(len, 3);
+        assert_eq!(tree.height(), 2);
+        assert_eq!(tree.len(), NonZeroLength::new(4).unwrap());
+        for (i, expected) in [1, 2, 3, 0].iter().enumerate() {
+            assert_eq!(*tree.get(i).unwrap(), *expected);
+        }
+
+        let (tree, len) = PerfectTree::<u8, Heap>::try_from_padded(vec![1, 2, 3, 4], || unreachable!()).unwrap();
+        assert_eq!(len, 4);
+        assert_eq!(tree.len(), NonZeroLength::new(4).unwrap());
+        for i in 0 .. 4 {
+            assert_eq!(*tree.get(i).unwrap(), i as u8 + 1);
+        }
+
+        let (tree, len) = PerfectTree::<u8, Heap>::try_from_padded(vec![7], || 0).unwrap();
+        assert_eq!(len, 1);
+        assert_eq!(tree.height(), 0);
+
+        assert!(PerfectTree::<u8, Heap>::try_from_padded(vec![], || 0).is_none());
+    }
+
+    /// Runs `f` on trees of every height from 0 to 5, with leaf `i` set to `i`.
+    fn for_each_height(mut f: impl FnMut(u8, PerfectTree<u8, Heap>)) {
+        for height in 0 ..= 5u8 {
+            let items: Vec<u8> = (0 .. 1u8 << height).collect();
+            let (tree, len) = PerfectTree::try_from_padded(items, || unreachable!()).unwrap();
+            assert_eq!(len, 1 << height);
+            f(height, tree)
+        }
+    }
+
+    /// Returns a height 2 tree of the items `0 .. 4`.
+    pub(super) fn tree4() -> PerfectTree<u8, Heap> {
+        let leaves: Vec<_> = (0u8 .. 4).map(PerfectTree::<u8, Heap>::new_leaf).collect();
+        let mut leaves = leaves.into_iter();
+        let mut next = || leaves.next().unwrap();
+        let left = PerfectTree::try_join(next(), next()).unwrap();
+        let right = PerfectTree::try_join(next(), next()).unwrap();
+        PerfectTree::try_join(left, right).unwrap()
+    }
+
+    /// Loads the tree saved at `offset` in `buf`.
+    pub(super) fn load(buf: &[u8], offset: Offset) -> Bag<PerfectTree<u8, Key<[u8]>>, Key<[u8]>> {
+        let key = Key::<[u8]>::from_blob(offset, &buf);
+        unsafe { Bag::from_raw_parts(key, ()) }
+    }
+
+    /// Like `load`, but mutably.
+    pub(super) fn load_mut(buf: &[u8], offset: Offset) -> Bag<PerfectTree<u8, PileMut<[u8]>>, PileMut<[u8]>> {
+        let key = PileMut::from(Key::<[u8]>::from_blob(offset, &buf));
+        unsafe { Bag::from_raw_parts(key, ()) }
+    }
+
+    thread_local! {
+        static STACK_LOW: std::cell::Cell<usize> = std::cell::Cell::new(usize::MAX);
+    }
+
+    /// Records how far down the stack has grown, for `max_stack_depth`.
+    fn probe_stack() {
+        let marker = 0u8;
+        let addr = &marker as *const u8 as usize;
+        STACK_LOW.with(|low| low.set(low.get().min(addr)));
+    }
+
+    /// Returns how many bytes deeper than the caller the stack got at any `probe_stack` in `f`.
+    ///
+    /// Used to check that walks of tall trees don't recurse once per level: unlike running on a
+    /// small stack, that fails regardless of how large each frame happens to be.
+    fn max_stack_depth(f: impl FnOnce()) -> usize {
+        let marker = 0u8;
+        let base = &marker as *const u8 as usize;
+        STACK_LOW.with(|low| low.set(usize::MAX));
+        f();
+        base.saturating_sub(STACK_LOW.with(|low| low.get()))
+    }
+
+    /// A leaf value that calls `probe_stack` when committed to or dropped.
+    #[derive(Debug)]
+    struct Probe(u8);
+
+    impl Commit for Probe {
+        type Commitment = u8;
+
+        fn to_commitment(&self) -> u8 {
+            probe_stack();
+            self.0
+        }
+    }
+
+    impl Drop for Probe {
+        fn drop(&mut self) {
+            probe_stack()
+        }
+    }
+
+    #[test]
+    fn every_height() {
+        for_each_height(|height, tree| {
+            let len = 1usize << height;
+            assert_eq!(tree.height(), height);
+            assert_eq!(tree.len(), NonZeroLength::new(len).unwrap());
+            for i in 0 .. len {
+                assert_eq!(*tree.get(i).unwrap(), i as u8);
+            }
+            assert!(tree.get(len).is_none());
+
+            // Commitments don't depend on how the tree was built, or where it's stored
+            let values: Vec<u8> = (0 .. len as u8).collect();
+            let refs: Vec<&u8> = values.iter().collect();
+            let expected = PerfectTree::commit_from_refs(&refs).unwrap().node_digest();
+            assert_eq!(tree.root_digest(), expected);
+            assert!(tree.verify_root(&expected));
+
+            let saver = OffsetSaver::new(&[][..]);
+            let (offset, buf) = saver.try_save(&tree).unwrap();
+
+            let bag = load(&buf, offset);
+            let loaded = bag.get();
+
+            assert_eq!(loaded.height(), height);
+            assert_eq!(loaded.len(), tree.len());
+            for i in 0 .. len {
+                assert_eq!(*loaded.get(i).unwrap(), i as u8);
+            }
+            assert!(loaded.get(len).is_none());
+            assert_eq!(loaded.root_digest(), expected);
+
+            // Re-saving the loaded tree gives the same bytes
+            let (resaved_offset, resaved) = OffsetSaver::new(&buf[..]).try_save(&*loaded).unwrap();
+            assert_eq!(resaved_offset, offset);
+            assert_eq!(resaved, buf);
+        });
+    }
+
+    #[test]
+    fn try_new_leaf_in_bounded() {
+        struct Bounded(usize);
+
+        impl Alloc for Bounded {
+            type Ptr = Heap;
+            type Error = ();
+
+            fn try_alloc<T: ?Sized + Pointee>(&mut self, src: impl Take<T>) -> Result<Bag<T, Heap>, ()> {
+                self.0 = self.0.checked_sub(1).ok_or(())?;
+                Ok(Heap::alloc(src))
+            }
+        }
+
+        let mut arena = Bounded(3);
+        let left = PerfectTree::<u8, Heap>::try_new_leaf_in(0, &mut arena).unwrap();
+        let right = PerfectTree::<u8, Heap>::try_new_leaf_in(1, &mut arena).unwrap();
+        let pair = Pair::try_join(left, right).ok().unwrap();
+        let tip = Tip::try_new_in(pair, &mut arena).unwrap();
+        assert_eq!(*tip.get_leaf(1).unwrap().get(), 1);
+
+        // The arena is full
+        assert!(PerfectTree::<u8, Heap>::try_new_leaf_in(2, &mut arena).is_err());
+    }
+
+    #[test]
+    fn commitment_stub() {
+        for_each_height(|height, tree| {
+            let root = tree.root_digest();
+            let stub = PerfectTree::<u8, (), Sha256Digest>::commitment_stub(root, Height::new(height).unwrap());
+            assert_eq!(stub.height(), height);
+            assert_eq!(stub.root_digest(), root);
+        });
+    }
+
+    #[test]
+    fn duplicate_positions() {
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![10, 11, 10, 12], || unreachable!()).unwrap();
+        assert_eq!(tree.duplicate_positions(), vec![(0, 2)]);
+
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![7, 1, 7, 7], || unreachable!()).unwrap();
+        assert_eq!(tree.duplicate_positions(), vec![(0, 2), (0, 3), (2, 3)]);
+
+        for_each_height(|_height, tree| {
+            assert!(tree.duplicate_positions().is_empty());
+        });
+    }
+
+    #[test]
+    fn diff() {
+        let leaves: Vec<u8> = (0 .. 8).collect();
+        let (a, _) = PerfectTree::<u8, Heap>::try_from_padded(leaves.clone(), || unreachable!()).unwrap();
+        let (b, _) = PerfectTree::<u8, Heap>::try_from_padded(leaves.clone(), || unreachable!()).unwrap();
+        assert_eq!(a.diff(&b), Vec::<usize>::new());
+
+        let mut changed = leaves.clone();
+        changed[5] = 42;
+        let (c, _) = PerfectTree::<u8, Heap>::try_from_padded(changed.clone(), || unreachable!()).unwrap();
+        assert_eq!(a.diff(&c), vec![5]);
+        assert_eq!(c.diff(&a), vec![5]);
+
+        changed[0] = 42;
+        changed[7] = 42;
+        let (d, _) = PerfectTree::<u8, Heap>::try_from_padded(changed, || unreachable!()).unwrap();
+        assert_eq!(a.diff(&d), vec![0, 5, 7]);
+    }
+
+    #[test]
+    fn find_by_digest() {
+        let (tree, _) = PerfectTree::<u8, Heap>::try_from_padded(vec![10, 11, 12, 11], || unreachable!()).unwrap();
+
+        let d = |n: u8| HashCommit::<u8, Sha256Digest>::new(&n).digest();
+        assert_eq!(tree.find_by_digest(&d(10)), Some(0));
+        assert_eq!(tree.find_by_digest(&d(12)), Some(2));
+
+        // First match wins
+        assert_eq!(tree.find_by_digest(&d(11)), Some(1));
+
+        assert_eq!(tree.find_by_digest(&d(13)), None);
+        assert_eq!(tree.find_by_digest(&tree.node_digest()), None);
+
+        let leaf = PerfectTree::<u8, Heap>::new_leaf(42);
+        assert_eq!(leaf.find_by_digest(&d(42)), Some(0));
+        assert_eq!(leaf.find_by_digest(&d(0)), None);
+    }
+
+    #[test]
+    fn tall_tree_commit() {
+        // Cold roots are hashed without recursing per level, yet match the level-by-level
+        // calculation of commit_from_refs.
+        //
+        // Height 16 is enough to tell: recursing once per level would add at least a return
+        // address per level, well over the 64 bytes of slack allowed between heights 1 and 16.
+        let mut depths = vec![];
+        for &height in [1u8, 3, 16].iter() {
+            let values: Vec<u8> = (0 .. 1usize << height).map(|i| i as u8).collect();
+            let items = values.iter().copied().map(Probe).collect();
+            let (tree, _) = PerfectTree::<Probe, Heap>::try_from_padded(items, || unreachable!()).unwrap();
+            assert!(tree.try_node_digest().is_none());
+
+            let refs: Vec<&u8> = values.iter().collect();
+            let expected = PerfectTree::commit_from_refs(&refs).unwrap().node_digest();
+
+            // Every leaf is hashed from the same loop, so a recursive calculation would show up
+            // as the stack reaching deeper on taller trees.
+            let mut digest = None;
+            depths.push(max_stack_depth(|| digest = Some(tree.node_digest())));
+            assert_eq!(digest, Some(expected));
+            assert_eq!(tree.to_commitment().try_node_digest(), Some(expected));
+
+            // Every digest below the root was cached along the way
+            if let Kind::Tip(tip) = tree.kind() {
+                let pair = tip.try_get_dirty_pair().ok().expect("heap tips are dirty");
+                assert!(pair.left().try_node_digest().is_some());
+                assert!(pair.right().try_node_digest().is_some());
+            }
+        }
+        assert!(depths[0] > 0);
+        assert!(depths[2] <= depths[0] + 64, "hashing recursed: {:?} bytes of stack", depths);
+    }
+
+    #[test]
+    fn dirty_node_count() {
+        for_each_height(|height, tree| {
+            let len = 1usize << height;
+            assert_eq!(tree.dirty_node_count(), 2 * len - 1);
+
+            let saver = OffsetSaver::new(&[][..]);
+            let (offset, buf) = saver.try_save(&tree).unwrap();
+
+            let bag = load(&buf, offset);
+            assert_eq!(bag.get().dirty_node_count(), 0);
+        });
+
+        // Mutating a loaded leaf only dirties the path to it
+        let tree = tree4();
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let mut bag = load_mut(&buf, offset);
+        let loaded = bag.get_mut();
+        assert_eq!(loaded.dirty_node_count(), 0);
+
+        assert_eq!(loaded.set_leaf(1, 11), Ok(1));
+        assert_eq!(loaded.dirty_node_count(), 3);
+    }
+
+    #[test]
+    fn hydrate() {
+        let tree = tree4();
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let size = <PerfectTree<u8, Offset> as Blob>::SIZE;
+        let start = offset.get() as usize;
+        let bytes = Bytes::<PerfectTree<u8, Offset>>::try_from(&buf[start .. start + size]).unwrap();
+        let blob = <PerfectTree<u8, Offset> as Blob>::decode_bytes(bytes).unwrap().trust();
+        assert_eq!(blob.height(), 2);
+
+        let map: &[u8] = &buf;
+        let hydrated: PerfectTree<u8, Key<[u8]>> = blob.hydrate(&map);
+        assert_eq!(hydrated.len(), tree.len());
+        for i in 0 .. 4 {
+            assert_eq!(*hydrated.get(i).unwrap(), i as u8);
+        }
+        assert_eq!(hydrated.node_digest(), tree.node_digest());
+    }
+
+    #[test]
+    fn get_owned() {
+        let mut tree = tree4();
+
+        let item: u8 = tree.get_owned(1).unwrap();
+        assert_eq!(item, 1);
+        assert_eq!(tree.get_owned(4), None);
+
+        // The tree is still usable
+        assert_eq!(tree.set_leaf(1, item + 10), Ok(1));
+        assert_eq!(tree.get_owned(1), Some(11));
+        assert_eq!(tree.into_get(1), Some(11));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "clean node missing digest")]
+    fn tip_new_unchecked_clean_without_digest() {
+        let pair: Bag<PairDyn<u8, Offset>, Offset> = unsafe { Bag::from_raw_parts(Offset::new(0), NonZeroHeight::MIN) };
+        let _tip = Tip::new_unchecked(None, pair);
+    }
+
+    #[test]
+    fn into_zone() {
+        let tree = tree4();
+        let digest = tree.node_digest();
+
+        let mut pile: &[u8] = &[];
+        let moved: PerfectTree<u8, PileMut<[u8]>> = tree.into_zone(&mut pile);
+        assert_eq!(moved.height(), 2);
+        for i in 0 .. 4 {
+            assert_eq!(*moved.get(i).unwrap(), i as u8);
+        }
+        assert_eq!(moved.node_digest(), digest);
+
+        let back: PerfectTree<u8, Heap> = moved.into_zone(&mut ());
+        assert_eq!(back.node_digest(), digest);
+        assert_eq!(back.into_get(3), Some(3));
+    }
+
+    #[test]
+    fn join_and_save() {
+        let leaf = |n| PerfectTree::<u8, Heap>::new_leaf(n);
+
+        let mut pile = vec![];
+        let left_root = save_new(&mut pile, &PerfectTree::try_join(leaf(0), leaf(1)).unwrap()).unwrap();
+        let saved = pile.clone();
+        let saved_len = saved.len();
+
+        let err = PerfectTree::<u8, PileMut<[u8]>>::join_and_save(&pile, left_root, leaf(2)).unwrap_err();
+        assert!(matches!(err, JoinAndSaveError::Join(JoinError::HeightMismatch { .. })),
+                "{:?}", err);
+
+        let err = PerfectTree::<u8, PileMut<[u8]>>::join_and_save(&pile, Offset::MAX, leaf(2)).unwrap_err();
+        assert!(matches!(err, JoinAndSaveError::Load(_)), "{:?}", err);
+
+        // The right tree doesn't have to be on the heap
+        let right = PerfectTree::try_join(leaf(2), leaf(3)).unwrap();
+        let mut right_pile: &[u8] = &[];
+        let right: PerfectTree<u8, PileMut<[u8]>> = right.into_zone(&mut right_pile);
+
+        let (root, blobs) = PerfectTree::<u8, PileMut<[u8]>>::join_and_save(&pile, left_root, right).unwrap();
+        pile.extend_from_slice(&blobs);
+
+        // The left tree was reused rather than copied: only the right tree's values and pair, a
+        // new pair, and the new root were appended.
+        assert_eq!(&pile[.. saved_len], &saved[..]);
+        assert_eq!(pile.len(), saved_len * 2 + <raw::Pair<u8, Offset> as Blob>::SIZE);
+
+        let bag = load(&pile, root);
+        let loaded = bag.get();
+        assert_eq!(loaded.height(), 2);
+        for i in 0 .. 4 {
+            assert_eq!(*loaded.get(i).unwrap(), i as u8);
+        }
+        assert_eq!(loaded.node_digest(), tree4().node_digest());
+    }
+
+    #[test]
+    fn self_describing_round_trip() {
+        let digest = Sha256Digest::from([0x42; 32]);
+        let raw = raw::Node::new(Some(digest), Offset::new(1234));
+        let tree: PerfectTree<u8, Offset> = unsafe { PerfectTree::from_raw_node(raw, Height::new(3).unwrap()) };
+
+        let buf = tree.encode_self_describing();
+        assert_eq!(&buf[.. 4], b"ptre");
+        assert_eq!(buf[4], 3);
+        assert_eq!(buf.len(), 4 + 1 + 32 + 8);
+
+        let decoded = PerfectTree::<u8, Offset>::decode_self_describing(&buf).unwrap();
+        assert_eq!(decoded.height(), 3);
+        assert_eq!(decoded.into_raw_node().into_raw_parts(), (Some(digest), Offset::new(1234)));
+    }
+
+    #[test]
+    fn self_describing_rejects_invalid() {
+        let raw = raw::Node::new(Some(Sha256Digest::default()), Offset::new(0));
+        let tree: PerfectTree<u8, Offset> = unsafe { PerfectTree::from_raw_node(raw, Height::ZERO) };
+        let buf = tree.encode_self_describing();
+
+        let mut bad_tag = buf.clone();
+        bad_tag[0] = b'x';
+        assert!(matches!(PerfectTree::<u8, Offset>::decode_self_describing(&bad_tag),
+                         Err(DecodeSelfDescribingError::Tag)));
+        assert!(matches!(PerfectTree::<u8, Offset>::decode_self_describing(&buf[.. 2]),
+                         Err(DecodeSelfDescribingError::Tag)));
+
+        assert!(matches!(PerfectTree::<u8, Offset>::decode_self_describing(&buf[.. buf.len() - 1]),
+                         Err(DecodeSelfDescribingError::Length { expected: 45, found: 44 })));
+
+        let mut bad_height = buf.clone();
+        bad_height[4] = Height::MAX.get() + 1;
+        assert!(matches!(PerfectTree::<u8, Offset>::decode_self_describing(&bad_height),
+                         Err(DecodeSelfDescribingError::Height(HeightError))));
+    }
+
+    #[test]
+    fn digest_at() {
+        let tree = tree4();
+        let leaf_digests: Vec<_> = (0u8 .. 4).map(|n| PerfectTree::<u8, Heap>::new_leaf(n).node_digest()).collect();
+        let (left_digest, right_digest) = match tree.kind() {
+            Kind::Tip(tip) => tip.get_pair().child_digests(),
+            Kind::Leaf(_) => unreachable!(),
+        };
+
+        let h = |n| Height::new(n).unwrap();
+        assert_eq!(tree.digest_at(tree.height(), 0), Some(tree.node_digest()));
+        assert_eq!(tree.digest_at(h(1), 0), Some(left_digest));
+        assert_eq!(tree.digest_at(h(1), 1), Some(right_digest));
+        for i in 0 .. 4 {
+            assert_eq!(tree.digest_at(h(0), i), Some(leaf_digests[i]));
+        }
+
+        assert_eq!(tree.digest_at(h(2), 1), None);
+        assert_eq!(tree.digest_at(h(1), 2), None);
+        assert_eq!(tree.digest_at(h(0), 4), None);
+        assert_eq!(tree.digest_at(h(0), usize::MAX), None);
+        assert_eq!(tree.digest_at(h(3), 0), None);
+    }
+
+    #[test]
+    fn child_digests() {
+        let left = PerfectTree::<u8, Heap>::new_leaf(1);
+        let right = PerfectTree::<u8, Heap>::new_leaf(2);
+        let expected = (left.node_digest(), right.node_digest());
+        let tree = PerfectTree::try_join(left, right).unwrap();
+
+        match tree.kind() {
+            Kind::Tip(tip) => assert_eq!(tip.get_pair().child_digests(), expected),
+            Kind::Leaf(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn into_owned_drops_once() {
+        use std::panic::{self, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let new_tree = || {
+            let leaves: Vec<_> = (0 .. 4).map(|_| PerfectTree::<Rc<()>, Heap>::new_leaf(Rc::clone(&counter))).collect();
+            let mut leaves = leaves.into_iter();
+            let mut next = || leaves.next().unwrap();
+            let left = PerfectTree::try_join(next(), next()).unwrap();
+            let right = PerfectTree::try_join(next(), next()).unwrap();
+            PerfectTree::try_join(left, right).unwrap()
+        };
+
+        let tree = new_tree();
+        assert_eq!(Rc::strong_count(&counter), 5);
+        let owned = tree.take_unsized(PerfectTreeDyn::into_owned);
+        assert_eq!(Rc::strong_count(&counter), 5);
+        drop(owned);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        // A panic before conversion drops the tree exactly once, via the RefOwn
+        let tree = new_tree();
+        let r = panic::catch_unwind(AssertUnwindSafe(|| {
+            tree.take_unsized(|_this: RefOwn<PerfectTreeDyn<Rc<()>, Heap>>| -> () { panic!() })
+        }));
+        assert!(r.is_err());
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn drop_tall_tree_iteratively() {
+        // Every leaf is dropped from the same loop, however tall the tree is. Dropping
+        // recursively would reach deeper into the stack with every level.
+        let depth = |height: u8| {
+            let items = (0 .. 1usize << height).map(|_| Probe(0)).collect();
+            let (tree, _) = PerfectTree::<Probe, Heap>::try_from_padded(items, || unreachable!()).unwrap();
+            max_stack_depth(|| drop(tree))
+        };
+        let (short, tall) = (depth(2), depth(14));
+        assert!(short > 0);
+        assert!(tall <= short + 64, "dropping recursed: {} vs {} bytes of stack", tall, short);
+    }
+
+    #[test]
+    fn into_halves() {
+        let tree = tree4();
+        let (left_digest, right_digest) = match tree.kind() {
+            Kind::Tip(tip) => tip.get_pair().child_digests(),
+            Kind::Leaf(_) => unreachable!(),
+        };
+
+        let (left, right) = tree.into_halves().unwrap();
+        assert_eq!(left.height(), 1);
+        assert_eq!(right.height(), 1);
+        assert_eq!(left.node_digest(), left_digest);
+        assert_eq!(right.node_digest(), right_digest);
+        for i in 0 .. 2 {
+            assert_eq!(*left.get(i).unwrap(), i as u8);
+            assert_eq!(*right.get(i).unwrap(), i as u8 + 2);
+        }
+
+        let (a, b) = left.into_halves().unwrap();
+        assert_eq!(a.into_get(0), Some(0));
+        assert_eq!(b.into_get(0), Some(1));
+
+        assert!(PerfectTree::<u8, Heap>::new_leaf(0).into_halves().is_none());
+    }
+
+    #[test]
+    fn try_size_validates_height() {
+        let node_size = <raw::Node<u8, Offset> as Blob>::SIZE;
+        let pair_size = <raw::Pair<u8, Offset> as Blob>::SIZE;
+
+        assert_eq!(PerfectTreeDyn::<u8, Offset>::try_size(Height::ZERO), Ok(node_size));
+        assert_eq!(PerfectTreeDyn::<u8, Offset>::try_size(Height::MAX), Ok(node_size));
+        assert_eq!(TipDyn::<u8, Offset>::try_size(NonZeroHeight::MAX), Ok(node_size));
+        assert_eq!(PairDyn::<u8, Offset>::try_size(NonZeroHeight::MIN), Ok(pair_size));
+
+        let bad_height = unsafe { Height::new_unchecked(Height::MAX.get() + 1) };
+        assert_eq!(PerfectTreeDyn::<u8, Offset>::try_size(bad_height), Err(HeightError));
+
+        let bad_height = unsafe { NonZeroHeight::new_unchecked(std::num::NonZeroU8::new(u8::MAX).unwrap()) };
+        assert_eq!(TipDyn::<u8, Offset>::try_size(bad_height), Err(HeightError));
+        assert_eq!(PairDyn::<u8, Offset>::try_size(bad_height), Err(HeightError));
+    }
+
+    #[test]
+    fn try_layout_validates_height() {
+        let layout = <PerfectTreeDyn<u8, Heap> as Pointee>::try_layout(Height::MAX).unwrap();
+        assert_eq!(layout, Layout::new::<raw::Node<u8, Heap>>());
+
+        let layout = <PairDyn<u8, Heap> as Pointee>::try_layout(NonZeroHeight::MIN).unwrap();
+        assert_eq!(layout, Layout::new::<raw::Pair<u8, Heap>>());
+
+        let bad_height = unsafe { Height::new_unchecked(Height::MAX.get() + 1) };
+        assert_eq!(<PerfectTreeDyn<u8, Heap> as Pointee>::try_layout(bad_height), Err(HeightError));
+    }
+
+    #[test]
+    fn try_metadata_rejects_invalid_len() {
+        let fat_ptr = |len: usize| -> *const PairDyn<u8, Heap> {
+            let ptr: *const [()] = ptr::slice_from_raw_parts(ptr::NonNull::dangling().as_ptr(), len);
+            unsafe { mem::transmute(ptr) }
+        };
+
+        assert_eq!(<PairDyn<u8, Heap> as Pointee>::try_metadata(fat_ptr(1)), Ok(NonZeroHeight::MIN));
+        assert_eq!(<PairDyn<u8, Heap> as Pointee>::try_metadata(fat_ptr(usize::from(Height::MAX.get()))), Ok(NonZeroHeight::MAX));
+        assert_eq!(<PairDyn<u8, Heap> as Pointee>::try_metadata(fat_ptr(0)), Err(MetadataError));
+        assert_eq!(<PairDyn<u8, Heap> as Pointee>::try_metadata(fat_ptr(usize::from(Height::MAX.get()) + 1)), Err(MetadataError));
+        assert_eq!(<PairDyn<u8, Heap> as Pointee>::try_metadata(fat_ptr(usize::MAX)), Err(MetadataError));
+    }
+
+    #[test]
+    fn get_cached() {
+        let tree = tree4();
+
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let zone = CachingZone::new(TracingZone::new(&buf[..]), 16);
+        let zone_ref = &zone;
+        let key = Key::<CachingZone<TracingZone<&[u8]>>>::from_blob(offset, &zone_ref);
+        let bag: Bag<PerfectTree<u8, Key<CachingZone<TracingZone<&[u8]>>>>, _> = unsafe { Bag::from_raw_parts(key, ()) };
+        let loaded = bag.get();
+        zone.inner().take_trace();
+
+        assert_eq!(*loaded.get(3).unwrap(), 3);
+        assert_eq!(zone.inner().take_trace().len(), 3);
+
+        // Second time around the whole path is served from the cache
+        assert_eq!(*loaded.get(3).unwrap(), 3);
+        assert!(zone.inner().take_trace().is_empty());
+
+        // Shares the tip pair with the first path
+        assert_eq!(*loaded.get(0).unwrap(), 0);
+        assert_eq!(zone.inner().take_trace().len(), 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invalid height metadata")]
+    fn make_fat_ptr_invalid_height() {
+        let height = std::num::NonZeroU8::new(Height::MAX.get() + 1).unwrap();
+        let height = unsafe { NonZeroHeight::new_unchecked(height) };
+        let _ = PairDyn::<u8, Heap>::make_fat_ptr(ptr::null(), height);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invalid height metadata")]
+    fn make_fat_ptr_mut_invalid_height() {
+        let height = unsafe { Height::new_unchecked(u8::MAX) };
+        let _ = PerfectTreeDyn::<u8, Heap>::make_fat_ptr_mut(ptr::null_mut(), height);
+    }
+
     #[test]
     fn test_commit() {
         /*
@@ -0,0 +1,233 @@
+//! Building perfect trees too large to hold in memory.
+
+use std::error;
+
+use hoard::bag::Bag;
+use hoard::load::{Load, LoadRef};
+use hoard::pointee::Pointee;
+use hoard::ptr::{AsZone, PtrClean};
+use hoard::ptr::key::{
+    Key, Offset, PileMut,
+    offset::{DynSaver, EMPTY_PILE, save_dyn, save_dyn_dirty},
+};
+use hoard::save::{Save, SaveRef};
+
+use crate::commit::{
+    Commit, Digest, HashCommit,
+    sha256::Sha256Digest,
+};
+
+use super::super::leaf::Leaf;
+use super::{Pair, PairDyn, PerfectTree, Tip};
+
+/// Pointers in the peaks of a `StreamingTreeBuilder`, all of them clean.
+type Pile = PileMut<'static, [u8]>;
+
+/// Builds a `PerfectTree` one leaf at a time, saving it to `Z` as it grows.
+///
+/// Each leaf value is saved as soon as it's pushed, and every pair as soon as both of its sides
+/// are complete. Only the roots of the completed subtrees, at most one per height, are kept in
+/// memory, as clean nodes pointing to what's already been saved.
+#[derive(Debug)]
+pub struct StreamingTreeBuilder<T, Z, D: Digest = Sha256Digest> {
+    saver: Z,
+
+    // Tallest first
+    peaks: Vec<PerfectTree<T, Pile, D>>,
+}
+
+impl<T, Z, D: Digest> StreamingTreeBuilder<T, Z, D> {
+    /// Creates a new builder, saving to `saver`.
+    pub fn new(saver: Z) -> Self {
+        Self {
+            saver,
+            peaks: vec![],
+        }
+    }
+
+    /// Returns the number of leaves pushed so far.
+    pub fn len(&self) -> usize {
+        self.peaks.iter().map(|peak| peak.height().len()).sum()
+    }
+
+    /// Returns true if no leaves have been pushed.
+    pub fn is_empty(&self) -> bool {
+        self.peaks.is_empty()
+    }
+
+    /// Returns the saver.
+    pub fn saver(&self) -> &Z {
+        &self.saver
+    }
+
+    /// Consumes the builder, returning the saver.
+    pub fn into_saver(self) -> Z {
+        self.saver
+    }
+}
+
+impl<T, Z, D: Digest> StreamingTreeBuilder<T, Z, D>
+where T: Commit + Save<Offset>,
+      Z: DynSaver,
+      Key<'static, [u8]>: From<T::PtrClean>,
+      &'static [u8]: AsZone<T::Zone>,
+      PairDyn<T, Pile, D>: SaveRef<Offset>,
+      Key<'static, [u8]>: From<<PairDyn<T, Pile, D> as LoadRef>::PtrClean>,
+      &'static [u8]: AsZone<<<PairDyn<T, Pile, D> as LoadRef>::PtrClean as PtrClean>::Zone>,
+      PerfectTree<T, Pile, D>: Save<Offset>,
+      Key<'static, [u8]>: From<<PerfectTree<T, Pile, D> as Load>::PtrClean>,
+      &'static [u8]: AsZone<<<PerfectTree<T, Pile, D> as Load>::PtrClean as PtrClean>::Zone>,
+{
+    /// Pushes a new leaf, saving every subtree it completes.
+    ///
+    /// If saving a subtree fails, the leaves pushed so far are kept, and the subtree is saved
+    /// again by the next call to `push` or `save_root`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree would be taller than `Height::MAX`.
+    pub fn push(&mut self, value: T) -> Result<(), Box<dyn error::Error>> {
+        let digest = HashCommit::<T::Commitment, D>::new(&value).digest();
+        let offset = save_dyn(EMPTY_PILE, &mut self.saver, &value)?;
+
+        self.peaks.push(Leaf::new_unchecked(Some(digest), saved(offset, ())).into());
+        self.carry()
+    }
+
+    /// Joins the last two peaks for as long as they're the same height.
+    fn carry(&mut self) -> Result<(), Box<dyn error::Error>> {
+        while let [.., left, right] = &self.peaks[..] {
+            if left.height() != right.height() {
+                break;
+            }
+
+            let right = self.peaks.pop().unwrap();
+            let left = self.peaks.pop().unwrap();
+            let pair = Pair::try_join(left, right).ok().expect("tree height overflow");
+            let digest = HashCommit::<Pair<T::Commitment, (), D>, D>::new(&pair).digest();
+
+            match save_dyn_dirty(EMPTY_PILE, &mut self.saver, &*pair) {
+                Ok(offset) => {
+                    let tip = Tip::new_unchecked(Some(digest), saved(offset, pair.height()));
+                    self.peaks.push(tip.into());
+                },
+                Err(err) => {
+                    let (left, right) = pair.into_split();
+                    self.peaks.push(left);
+                    self.peaks.push(right);
+                    return Err(err);
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the root of the tree, returning its offset.
+    ///
+    /// Returns `Ok(None)` if the number of leaves pushed isn't a power of two.
+    pub fn save_root(&mut self) -> Result<Option<Offset>, Box<dyn error::Error>> {
+        self.carry()?;
+        match &self.peaks[..] {
+            [root] => save_dyn_dirty(EMPTY_PILE, &mut self.saver, root).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Saves the root of the tree, returning its offset along with the saver.
+    ///
+    /// Same as `save_root` followed by `into_saver`: the offset is `None` if the number of leaves
+    /// pushed isn't a power of two.
+    pub fn finish(mut self) -> Result<(Option<Offset>, Z), Box<dyn error::Error>> {
+        let root = self.save_root()?;
+        Ok((root, self.saver))
+    }
+}
+
+/// Makes a clean bag for a blob that has already been saved.
+///
+/// Peaks are never loaded, only saved again by `save_dyn_dirty`, which reuses their offsets
+/// without reading them. So the key's map doesn't matter, and is left empty.
+fn saved<U: ?Sized + Pointee>(offset: Offset, metadata: U::Metadata) -> Bag<U, Pile> {
+    let key = Key::from_blob(offset, &EMPTY_PILE);
+
+    // SAFETY: `offset` was just returned by saving a `U` with this metadata.
+    unsafe { Bag::from_raw_parts(key.into(), metadata) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hoard::ptr::Heap;
+    use hoard::ptr::key::offset::{OffsetSaver, WriteSaver};
+
+    use super::super::tests::load;
+
+    #[test]
+    fn push_and_load() {
+        let mut builder = StreamingTreeBuilder::<u8, _>::new(OffsetSaver::empty());
+        for i in 0 .. 16 {
+            builder.push(i).unwrap();
+        }
+        assert_eq!(builder.len(), 16);
+
+        // Only the root is left in memory
+        assert_eq!(builder.peaks.len(), 1);
+
+        let (offset, saver) = builder.finish().unwrap();
+        let offset = offset.unwrap();
+        let pile = saver.finish();
+
+        let (expected, _) = PerfectTree::<u8, Heap>::try_from_padded((0 .. 16).collect(), || unreachable!()).unwrap();
+
+        let bag = load(&pile, offset);
+        let loaded = bag.get();
+        assert_eq!(loaded.height(), 4);
+        assert_eq!(loaded.node_digest(), expected.node_digest());
+        for i in 0 .. 16 {
+            assert_eq!(*loaded.get(i).unwrap(), i as u8);
+        }
+
+        // Same blobs as saving the whole tree at once, minus the unused padding
+        let mut builder = StreamingTreeBuilder::<u8, _>::new(WriteSaver::new(vec![]));
+        for i in 0 .. 16 {
+            builder.push(i).unwrap();
+        }
+        let (root, saver) = builder.finish().unwrap();
+        assert_eq!(root, Some(offset));
+        assert_eq!(saver.into_inner(), pile);
+    }
+
+    #[test]
+    fn save_root_not_power_of_two() {
+        let mut builder = StreamingTreeBuilder::<u8, _>::new(OffsetSaver::empty());
+        assert!(builder.is_empty());
+        assert_eq!(builder.save_root().unwrap(), None);
+
+        for i in 0 .. 3 {
+            builder.push(i).unwrap();
+        }
+        assert_eq!(builder.save_root().unwrap(), None);
+        assert_eq!(builder.len(), 3);
+        assert!(!builder.is_empty());
+
+        builder.push(3).unwrap();
+        assert!(builder.save_root().unwrap().is_some());
+        assert!(builder.finish().unwrap().0.is_some());
+    }
+
+    #[test]
+    fn push_error_keeps_leaves() {
+        // Room for both leaf values, but not the pair joining them
+        let mut buf = [0u8; 2];
+        let mut builder = StreamingTreeBuilder::<u8, _>::new(WriteSaver::new(&mut buf[..]));
+
+        builder.push(0).unwrap();
+        assert!(builder.push(1).is_err());
+        assert_eq!(builder.len(), 2);
+        assert_eq!(builder.peaks.len(), 2);
+
+        assert!(builder.save_root().is_err());
+        assert_eq!(builder.peaks.len(), 2);
+    }
+}
@@ -0,0 +1,295 @@
+//! Read-only views of perfect trees stored in a pile.
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use thiserror::Error;
+
+use hoard::bag::Bag;
+use hoard::blob::{Blob, Bytes};
+use hoard::load::Load;
+use hoard::ptr::PtrClean;
+use hoard::ptr::key::{Key, Map, Offset, offset::regions_overlap};
+
+use crate::commit::{
+    Digest,
+    sha256::Sha256Digest,
+};
+
+use super::super::height::Height;
+use super::super::raw;
+use super::PerfectTree;
+
+/// A borrowed view of a `PerfectTree` saved in a byte slice.
+///
+/// Nodes are decoded on demand directly from the slice as the tree is walked; no `Bag`s or owned
+/// tree nodes are ever allocated.
+///
+/// Piles are append-only, so every pointer must point to a blob that ends before the blob
+/// containing it. This is checked as the tree is walked, rejecting cyclic pointers.
+#[derive(Debug)]
+pub struct PerfectTreeView<'a, T, D: Digest = Sha256Digest> {
+    marker: PhantomData<fn() -> T>,
+    pile: &'a [u8],
+    root: Offset,
+    digest: D,
+    ptr: Offset,
+    height: Height,
+}
+
+/// Returned when a `PerfectTreeView` encounters an invalid pile.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ViewError {
+    #[error("blob at offset {0:?} out of bounds")]
+    OutOfBounds(Offset),
+
+    #[error("invalid blob at offset {0:?}")]
+    Decode(Offset),
+
+    #[error("blob at offset {0:?} is cyclic or aliased")]
+    Aliased(Offset),
+}
+
+impl<'a, T: Blob, D: Digest> PerfectTreeView<'a, T, D> {
+    /// Creates a new view from a `PerfectTree` blob at `root`.
+    pub fn new(pile: &'a [u8], root: Offset) -> Result<Self, ViewError> {
+        let tree = get_blob::<PerfectTree<T, Offset, D>>(pile, root)?;
+        let height = tree.height();
+        let (digest, ptr) = tree.into_raw_node().into_raw_parts();
+
+        Ok(Self {
+            marker: PhantomData,
+            pile,
+            root,
+            digest: digest.ok_or(ViewError::Decode(root))?,
+            ptr,
+            height,
+        })
+    }
+
+    /// Returns the height of the tree.
+    pub fn height(&self) -> Height {
+        self.height
+    }
+
+    /// Returns the number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.height.len()
+    }
+
+    /// Always false, as a perfect tree has at least one item.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the digest of the root node.
+    pub fn node_digest(&self) -> D {
+        self.digest
+    }
+
+    /// Gets the item at `idx`, returning `None` if the index is out of range.
+    pub fn get(&self, mut idx: usize) -> Result<Option<T>, ViewError> {
+        if idx >= self.len() {
+            return Ok(None);
+        }
+
+        let mut parent = self.root;
+        let mut ptr = self.ptr;
+        for height in (0 .. self.height.get()).rev() {
+            let pair = get_child::<raw::Pair<T, Offset, D>>(self.pile, parent, ptr)?;
+
+            let half = 1 << height;
+            parent = ptr;
+            ptr = if idx < half {
+                pair.left.ptr
+            } else {
+                idx -= half;
+                pair.right.ptr
+            };
+        }
+
+        get_child::<T>(self.pile, parent, ptr).map(Some)
+    }
+
+    /// Walks the entire tree, checking that every node is valid and no blob is reachable twice.
+    ///
+    /// Takes O(n log n) time in the number of nodes.
+    pub fn validate(&self) -> Result<(), ViewError> {
+        // Regions visited so far, by start
+        let mut visited = BTreeMap::new();
+        let mut stack = vec![(self.root, self.ptr, self.height.get())];
+
+        while let Some((parent, ptr, height)) = stack.pop() {
+            let size = if height > 0 {
+                let pair = get_child::<raw::Pair<T, Offset, D>>(self.pile, parent, ptr)?;
+                stack.push((ptr, pair.right.ptr, height - 1));
+                stack.push((ptr, pair.left.ptr, height - 1));
+                <raw::Pair<T, Offset, D> as Blob>::SIZE
+            } else {
+                get_child::<T>(self.pile, parent, ptr)?;
+                T::SIZE
+            };
+
+            // Visited regions never overlap each other, so only the closest region starting at or
+            // before this one, and the closest one starting after it, can overlap it.
+            let region = ptr.region(size);
+            let before = visited.range(..= region.start).next_back();
+            let after = visited.range(region.start + 1 ..).next();
+            if before.into_iter().chain(after)
+                     .any(|(&start, &end)| regions_overlap(region.clone(), start .. end))
+            {
+                return Err(ViewError::Aliased(ptr));
+            }
+            if !region.is_empty() {
+                visited.insert(region.start, region.end);
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the tree from the pile, after checking with `validate` that it really is a tree.
+    ///
+    /// A pile can be crafted with nodes that are reachable more than once, making a DAG that
+    /// looks like a tree. Loading a pile directly trusts it not to; this rejects such piles up
+    /// front, at the cost of walking the whole tree once.
+    pub fn load<U>(&self) -> Result<Bag<PerfectTree<U, Key<'a, [u8]>, D>, Key<'a, [u8]>>, ViewError>
+        where U: Load<Blob = T>
+    {
+        self.validate()?;
+        let key = Key::from_blob(self.root, &self.pile);
+        Ok(unsafe { Bag::from_raw_parts(key, ()) })
+    }
+}
+
+/// Gets a blob pointed to from the blob at `parent`, which must come before it in the pile.
+fn get_child<T: Blob>(pile: &[u8], parent: Offset, ptr: Offset) -> Result<T, ViewError> {
+    let blob = get_blob::<T>(pile, ptr)?;
+    if ptr.region(T::SIZE).end <= parent.region(0).start {
+        Ok(blob)
+    } else {
+        Err(ViewError::Aliased(ptr))
+    }
+}
+
+fn get_blob<T: Blob>(pile: &[u8], offset: Offset) -> Result<T, ViewError> {
+    pile.get_blob_with::<T, _, _>(offset, (), |bytes: Bytes<'_, T>| T::decode_bytes(bytes))
+        .map_err(|_| ViewError::OutOfBounds(offset))?
+        .map(|blob| blob.trust())
+        .map_err(|_| ViewError::Decode(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hoard::ptr::key::offset::OffsetSaver;
+
+    use super::super::tests::tree4;
+
+    #[test]
+    fn view_get() {
+        let tree = tree4();
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let view = PerfectTreeView::<u8>::new(&buf, offset).unwrap();
+        assert_eq!(view.height(), tree.height());
+        assert_eq!(view.len(), 4);
+        assert!(!view.is_empty());
+        assert_eq!(view.node_digest(), tree.node_digest());
+
+        for i in 0 .. 4 {
+            assert_eq!(view.get(i).unwrap(), Some(*tree.get(i).unwrap()));
+        }
+        assert_eq!(view.get(4), Ok(None));
+        assert_eq!(view.get(usize::MAX), Ok(None));
+    }
+
+    #[test]
+    fn view_invalid_pile() {
+        let tree = tree4();
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, mut buf) = saver.try_save(&tree).unwrap();
+
+        // Truncated root
+        assert_eq!(PerfectTreeView::<u8>::new(&buf[.. buf.len() - 1], offset).unwrap_err(),
+                   ViewError::OutOfBounds(offset));
+
+        // Root pointing past the end of the pile
+        let bad_ptr = Offset::new(buf.len() as u64);
+        let ptr_start = offset.get() as usize + <Sha256Digest as Blob>::SIZE;
+        buf[ptr_start .. ptr_start + 8].copy_from_slice(&bad_ptr.get().to_le_bytes());
+
+        let view = PerfectTreeView::<u8>::new(&buf, offset).unwrap();
+        assert_eq!(view.get(0), Err(ViewError::OutOfBounds(bad_ptr)));
+    }
+
+    #[test]
+    fn view_cyclic_pile() {
+        let tree = tree4();
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let view = PerfectTreeView::<u8>::new(&buf, offset).unwrap();
+        assert_eq!(view.validate(), Ok(()));
+        let pair = view.ptr;
+
+        let node_size = <raw::Node<u8, Offset> as Blob>::SIZE;
+
+        // Root pointing back to itself
+        let mut cyclic = buf.clone();
+        set_ptr(&mut cyclic, offset.get() as usize, offset);
+        let view = PerfectTreeView::<u8>::new(&cyclic, offset).unwrap();
+        assert!(view.get(0).is_err());
+        assert!(view.validate().is_err());
+
+        // Tip pair pointing back to itself
+        let mut cyclic = buf.clone();
+        set_ptr(&mut cyclic, pair.get() as usize, pair);
+        let view = PerfectTreeView::<u8>::new(&cyclic, offset).unwrap();
+        assert_eq!(view.get(0), Err(ViewError::Aliased(pair)));
+        assert_eq!(view.validate(), Err(ViewError::Aliased(pair)));
+
+        // Both halves of the tip pair pointing to the same subtree
+        let left = get_blob::<raw::Pair<u8, Offset>>(&buf, pair).unwrap().left.ptr;
+        let mut aliased = buf.clone();
+        set_ptr(&mut aliased, pair.get() as usize + node_size, left);
+        let view = PerfectTreeView::<u8>::new(&aliased, offset).unwrap();
+        assert_eq!(view.get(3).unwrap(), Some(1));
+        assert!(matches!(view.validate(), Err(ViewError::Aliased(_))));
+    }
+
+    #[test]
+    fn load_cyclic_pile() {
+        let tree = tree4();
+        let saver = OffsetSaver::new(&[][..]);
+        let (offset, buf) = saver.try_save(&tree).unwrap();
+
+        let view = PerfectTreeView::<u8>::new(&buf, offset).unwrap();
+        let top = get_blob::<raw::Pair<u8, Offset>>(&buf, view.ptr).unwrap();
+        let loaded = view.load::<u8>().unwrap();
+        for i in 0 .. 4 {
+            assert_eq!(*loaded.get().get(i).unwrap(), *tree.get(i).unwrap());
+        }
+
+        // Tip pointing back to the root; loading errors instead of recursing
+        let mut cyclic = buf.clone();
+        set_ptr(&mut cyclic, offset.get() as usize, offset);
+        let view = PerfectTreeView::<u8>::new(&cyclic, offset).unwrap();
+        assert_eq!(view.load::<u8>().unwrap_err(), ViewError::Aliased(offset));
+
+        // The first leaf of the right subtree pointing into the left subtree's pair, one level
+        // up. The pointer still points backwards, so only the visited regions catch it.
+        let (left_pair, right_pair) = (top.left.ptr, top.right.ptr);
+        let mut dag = buf.clone();
+        set_ptr(&mut dag, right_pair.get() as usize, left_pair);
+        let view = PerfectTreeView::<u8>::new(&dag, offset).unwrap();
+        assert_eq!(view.load::<u8>().unwrap_err(), ViewError::Aliased(left_pair));
+    }
+
+    fn set_ptr(buf: &mut Vec<u8>, node: usize, ptr: Offset) {
+        let ptr_start = node + <Sha256Digest as Blob>::SIZE;
+        buf[ptr_start .. ptr_start + 8].copy_from_slice(&ptr.get().to_le_bytes());
+    }
+}
@@ -1,4 +1,3 @@
-use std::cell::Cell;
 use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
 use std::error;
@@ -17,13 +16,102 @@ use crate::commit::{
 };
 
 /// A raw, untyped, tree node.
+///
+/// # Thread safety
+///
+/// The cached digest is interior mutable, so that it can be filled in through a shared reference.
+/// By default it's a `Cell`: nodes, and the trees built from them, are `Send` if `T`, `P` and `D`
+/// are, but never `Sync`. With the `atomic` feature the cache is synchronized instead, and nodes
+/// are also `Sync` if `T`, `P` and `D` are.
+///
+/// Note that `Heap` is neither `Send` nor `Sync`, so heap allocated trees are confined to a single
+/// thread either way.
 #[derive(Debug)]
 pub struct Node<T, P, D: Digest = Sha256Digest> {
     marker: PhantomData<T>,
-    digest: Cell<Option<D>>,
+    digest: DigestCell<D>,
     pub ptr: P,
 }
 
+#[cfg(not(feature = "atomic"))]
+use std::cell::Cell;
+
+#[cfg(not(feature = "atomic"))]
+type DigestCell<D> = Cell<Option<D>>;
+
+#[cfg(feature = "atomic")]
+type DigestCell<D> = self::atomic::AtomicDigest<D>;
+
+#[cfg(feature = "atomic")]
+mod atomic {
+    use std::cell::UnsafeCell;
+    use std::fmt;
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    const EMPTY: u8 = 0;
+    const WRITING: u8 = 1;
+    const FULL: u8 = 2;
+
+    /// A digest cache that can be filled in from multiple threads.
+    ///
+    /// The digest can only be set once through a shared reference. That's fine, as any digest
+    /// set later would be of the same data; `take` requires a unique reference.
+    pub struct AtomicDigest<D> {
+        state: AtomicU8,
+        digest: UnsafeCell<Option<D>>,
+    }
+
+    // SAFETY: the digest is only read once FULL has been published, and written either while
+    // WRITING is held, or through a unique reference.
+    unsafe impl<D: Send> Send for AtomicDigest<D> {}
+    unsafe impl<D: Send + Sync> Sync for AtomicDigest<D> {}
+
+    impl<D: Copy> AtomicDigest<D> {
+        pub fn get(&self) -> Option<D> {
+            if self.state.load(Ordering::Acquire) == FULL {
+                unsafe { *self.digest.get() }
+            } else {
+                None
+            }
+        }
+
+        pub fn set(&self, digest: Option<D>) {
+            if let Some(digest) = digest {
+                if self.state.compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                    unsafe { *self.digest.get() = Some(digest) };
+                    self.state.store(FULL, Ordering::Release);
+                }
+            }
+        }
+
+        pub fn take(&mut self) -> Option<D> {
+            *self.state.get_mut() = EMPTY;
+            self.digest.get_mut().take()
+        }
+
+        pub fn into_inner(self) -> Option<D> {
+            self.digest.into_inner()
+        }
+    }
+
+    impl<D> From<Option<D>> for AtomicDigest<D> {
+        fn from(digest: Option<D>) -> Self {
+            Self {
+                state: AtomicU8::new(if digest.is_some() { FULL } else { EMPTY }),
+                digest: UnsafeCell::new(digest),
+            }
+        }
+    }
+
+    impl<D: Copy + fmt::Debug> fmt::Debug for AtomicDigest<D> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_tuple("AtomicDigest")
+             .field(&self.get())
+             .finish()
+        }
+    }
+}
+
 /// A pair of left and right `Node`\'s.
 #[derive(Debug)]
 pub struct Pair<T, P, D: Digest = Sha256Digest> {
@@ -32,6 +120,13 @@ pub struct Pair<T, P, D: Digest = Sha256Digest> {
 }
 
 impl<T, P, D: Digest> Pair<T, P, D> {
+    /// Creates a new pair from left and right nodes.
+    ///
+    /// Each node is expected to already uphold its own invariants; see `Node::new_checked`.
+    pub fn new(left: Node<T, P, D>, right: Node<T, P, D>) -> Self {
+        Self { left, right }
+    }
+
     pub fn split_mut(&mut self) -> (&mut Node<T, P, D>, &mut Node<T, P, D>) {
         (&mut self.left,
          &mut self.right)
@@ -62,6 +157,22 @@ impl<T, P, D: Digest> Node<T, P, D> {
 }
 
 impl<T, P: Ptr, D: Digest> Node<T, P, D> {
+    /// Creates a new node, checking that a clean pointer has a digest.
+    ///
+    /// Clean data can't be hashed without loading it, so a clean node must always carry its
+    /// digest. The check is only done in debug builds.
+    #[track_caller]
+    pub fn new_checked(digest: Option<D>, ptr: P) -> Self {
+        debug_assert!(digest.is_some() || !Self::is_clean(&ptr),
+                      "clean node missing digest");
+        Self::new(digest, ptr)
+    }
+
+    fn is_clean(ptr: &P) -> bool {
+        // SAFETY: () is zero-sized, so the pointer is never dereferenced
+        unsafe { ptr.try_get_dirty::<()>(()).is_err() }
+    }
+
     pub unsafe fn get<U: ?Sized>(&self, metadata: U::Metadata) -> MaybeValid<Ref<U>>
         where U: LoadRef,
               P::Zone: AsZone<U::Zone>,
@@ -197,6 +308,8 @@ where T: Load,
 mod tests {
     use super::*;
 
+    use hoard::ptr::Heap;
+
     #[test]
     fn test_digest() {
         let node: Node<u8, ()> = Node::new(None, ());
@@ -207,6 +320,55 @@ mod tests {
         assert_eq!(node.digest().unwrap(), digest);
     }
 
+    #[test]
+    fn new_checked() {
+        let node: Node<u8, ()> = Node::new_checked(Some(Default::default()), ());
+        assert!(node.digest().is_some());
+
+        let (ptr, ()) = Heap::alloc(42u8).into_raw_parts();
+        let mut node: Node<u8, Heap> = Node::new_checked(None, ptr);
+        assert!(node.digest().is_none());
+        unsafe { node.ptr.dealloc::<u8>(()) };
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "clean node missing digest")]
+    fn new_checked_clean_without_digest() {
+        let _node: Node<u8, ()> = Node::new_checked(None, ());
+    }
+
+    #[test]
+    fn send_sync() {
+        use hoard::ptr::key::Offset;
+
+        use crate::collections::perfecttree::PerfectTree;
+
+        fn assert_send<T: Send>() {}
+        assert_send::<PerfectTree<u8, Offset>>();
+
+        #[cfg(feature = "atomic")]
+        {
+            fn assert_send_sync<T: Send + Sync>() {}
+            assert_send_sync::<Node<u8, Offset>>();
+            assert_send_sync::<PerfectTree<u8, Offset>>();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "atomic")]
+    fn atomic_digest() {
+        let node: Node<u8, ()> = Node::new(None, ());
+        assert!(node.digest().is_none());
+
+        let digest = Sha256Digest::default();
+        node.set_digest(digest);
+        assert_eq!(node.digest(), Some(digest));
+
+        let (digest, ()) = node.into_raw_parts();
+        assert_eq!(digest, Some(Sha256Digest::default()));
+    }
+
     #[test]
     fn test_blob_encode() {
         let node: Node<u8, u32> = Node::new(Some(Default::default()), 32);
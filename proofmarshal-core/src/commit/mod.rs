@@ -4,6 +4,7 @@ use std::any;
 use std::convert::TryFrom;
 use std::fmt;
 use std::hash;
+use std::io;
 use std::marker::PhantomData;
 use std::mem::{self, MaybeUninit};
 use std::slice;
@@ -17,6 +18,9 @@ mod impls;
 pub mod sha256;
 pub use self::sha256::*;
 
+pub mod truncated;
+pub use self::truncated::*;
+
 pub trait Digest : Primitive + Default + AsRef<[u8]> + AsMut<[u8]> + Eq
 {
     type Hasher : Default + Hasher<Output = Self>;
@@ -58,10 +62,69 @@ pub trait Hasher {
     fn finish(self) -> Self::Output;
 }
 
+/// Domain separation for commitments.
+///
+/// Hashes computed within a non-empty domain are prefixed by the hash of `DOMAIN`, twice, as in
+/// BIP340 tagged hashes, so that a digest computed in one application can't be replayed in
+/// another. The prefix has the same length whatever the tag, so no domain is a prefix of another.
+///
+/// Domains are only supported by `HashCommit::new_in` and `HashCommit::from_blob_in`, and by
+/// `PerfectTreeDyn::node_digest_in` on top of them. Everything else, including cached tree
+/// digests, MMR roots and proofs, commits in the default domain.
+pub trait CommitContext {
+    const DOMAIN: &'static [u8];
+}
+
+/// The default, empty, domain.
+///
+/// Digests computed in this domain are identical to digests computed without a domain.
+impl CommitContext for () {
+    const DOMAIN: &'static [u8] = b"";
+}
+
+/// A scheme for hashing the leaves and inner nodes of merkle trees.
+///
+/// Trees normally hash their nodes as commitments. A `TreeHash` scheme calculates the root in a
+/// different format instead, eg. to match roots calculated by other implementations.
+pub trait TreeHash<D: Digest> {
+    /// Hashes a leaf value.
+    fn hash_leaf<T: ?Sized + Commit>(leaf: &T) -> D;
+
+    /// Hashes an inner node from the digests of its children.
+    fn hash_node(left: &D, right: &D) -> D;
+}
+
+/// Merkle tree hashing as specified by RFC 6962, Certificate Transparency.
+///
+/// Leaves are hashed as `H(0x00 || commitment)`, and inner nodes as `H(0x01 || left || right)`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rfc6962;
+
+impl<D: Digest> TreeHash<D> for Rfc6962 {
+    fn hash_leaf<T: ?Sized + Commit>(leaf: &T) -> D {
+        let mut hasher = D::Hasher::default();
+        hasher.hash_bytes(&[0x00]);
+        leaf.hash_commitment_with(hasher)
+    }
+
+    fn hash_node(left: &D, right: &D) -> D {
+        let mut hasher = D::Hasher::default();
+        hasher.hash_bytes(&[0x01]);
+        hasher.hash_bytes(left.as_ref());
+        hasher.hash_bytes(right.as_ref());
+        hasher.finish()
+    }
+}
+
 /// A type for which there exists a canonical fixed-size commitment.
 pub trait Commit {
     type Commitment : 'static + Blob;
 
+    /// The exact length of the commitment bytes hashed by `hash_commitment_with`.
+    ///
+    /// Useful for sizing buffers in proof encoders.
+    const VERBATIM_LEN: usize = <Self::Commitment as Blob>::SIZE;
+
     fn to_commitment(&self) -> Self::Commitment;
 
     fn encode_commitment_bytes<'a>(&self, dst: BytesUninit<'a, Self::Commitment>) -> Bytes<'a, Self::Commitment> {
@@ -72,6 +135,14 @@ pub trait Commit {
         hasher.hash_blob(&self.to_commitment());
         hasher.finish()
     }
+
+    /// Writes the commitment bytes hashed by `hash_commitment_with` to `dst`.
+    ///
+    /// Exactly `VERBATIM_LEN` bytes are written, so the commitment can be fed into an external
+    /// hash pipeline.
+    fn write_verbatim(&self, dst: &mut impl io::Write) -> io::Result<()> {
+        dst.write_all(&self.to_commitment().to_blob_bytes())
+    }
 }
 
 /// Variable-length commitments.
@@ -118,7 +189,7 @@ macro_rules! impl_commit {
 
 impl_commit! {
     !, (),
-    bool,
+    bool, char,
     u8, u16, u32, u64, u128,
     i8, i16, i32, i64, i128,
 }
@@ -191,6 +262,44 @@ impl<T: ?Sized + BlobDyn, D: Digest> HashCommit<T, D> {
     }
 }
 
+impl<T: Blob, D: Digest> HashCommit<T, D> {
+    /// Creates a new hash commit from a commitment blob, within the domain `C`.
+    ///
+    /// Blobs that fit in `D` are only used verbatim in the empty domain; in every other domain the
+    /// blob is always hashed, prefixed by the hash of the domain tag, twice.
+    pub fn from_blob_in<C: CommitContext>(blob: &T) -> Self {
+        if C::DOMAIN.is_empty() && T::SIZE <= mem::size_of::<D>() {
+            let mut digest = D::default();
+            let dst = BytesUninit::<T>::from_bytes(
+                &mut digest.as_mut()[.. T::SIZE],
+                (),
+            ).unwrap();
+
+            blob.encode_bytes(dst);
+            Self::from_digest(digest)
+        } else {
+            let mut hasher = D::Hasher::default();
+            if !C::DOMAIN.is_empty() {
+                let mut tag_hasher = D::Hasher::default();
+                tag_hasher.hash_bytes(C::DOMAIN);
+                let tag = tag_hasher.finish();
+
+                hasher.hash_bytes(tag.as_ref());
+                hasher.hash_bytes(tag.as_ref());
+            }
+            hasher.hash_blob(blob);
+            Self::from_digest(hasher.finish())
+        }
+    }
+
+    /// Creates a new hash commit to a value, within the domain `C`.
+    pub fn new_in<C: CommitContext, U>(value: &U) -> Self
+        where U: ?Sized + Commit<Commitment = T>
+    {
+        Self::from_blob_in::<C>(&value.to_commitment())
+    }
+}
+
 impl<T: ?Sized, D: Digest> HashCommit<T, D> {
     pub fn from_digest(digest: D) -> Self {
         Self {
@@ -277,6 +386,66 @@ mod tests {
          &[1u8,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32])
     }
 
+    #[test]
+    fn hash_commit_in_domain() {
+        struct Foo;
+        impl CommitContext for Foo {
+            const DOMAIN: &'static [u8] = b"foo";
+        }
+
+        let value = [1u8; 33];
+        assert_eq!(HashCommit::<[u8; 33]>::new_in::<(), _>(&value),
+                   HashCommit::new(&value));
+        assert_eq!(HashCommit::<u8>::new_in::<(), _>(&1u8),
+                   HashCommit::new(&1u8));
+
+        assert_ne!(HashCommit::<u8>::new_in::<Foo, _>(&1u8),
+                   HashCommit::new(&1u8));
+        assert_ne!(HashCommit::<[u8; 33]>::new_in::<Foo, _>(&value),
+                   HashCommit::new(&value));
+
+        // A shorter tag followed by the blob's first byte isn't the same domain
+        struct Fo;
+        impl CommitContext for Fo {
+            const DOMAIN: &'static [u8] = b"fo";
+        }
+
+        let mut shifted = [1u8; 33];
+        shifted[0] = b'o';
+        assert_ne!(HashCommit::<[u8; 33]>::new_in::<Fo, _>(&shifted).digest(),
+                   HashCommit::<[u8; 32]>::new_in::<Foo, _>(&[1u8; 32]).digest());
+    }
+
+    #[test]
+    fn rfc6962_tree_hash() {
+        use hex_literal::hex;
+
+        // Test vectors from the Certificate Transparency reference implementation
+        let empty: Sha256Digest = Rfc6962::hash_leaf(&());
+        assert_eq!(empty.as_bytes(),
+                   &hex!("6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"));
+
+        let zero: Sha256Digest = Rfc6962::hash_leaf(&0u8);
+        assert_eq!(Rfc6962::hash_node(&empty, &zero).as_bytes(),
+                   &hex!("fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"));
+    }
+
+    #[test]
+    fn write_verbatim() {
+        #[track_caller]
+        fn t<T: Commit>(value: T, expected: &[u8]) {
+            let mut buf = vec![];
+            value.write_verbatim(&mut buf).unwrap();
+            assert_eq!(buf.len(), T::VERBATIM_LEN);
+            assert_eq!(buf, expected);
+        }
+
+        t((), &[]);
+        t(true, &[1]);
+        t(0x1234u16, &[0x34, 0x12]);
+        t([1u8; 33], &[1; 33]);
+    }
+
     #[test]
     fn long_hash_commit() {
         t([1u8,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32,33],
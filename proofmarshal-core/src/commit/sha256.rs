@@ -1,5 +1,6 @@
 //! SHA256 support.
 
+use std::array::TryFromSliceError;
 use std::convert::TryFrom;
 
 use hoard::blob::{Bytes, BytesUninit};
@@ -12,6 +13,55 @@ use super::{Digest, Hasher};
 #[repr(transparent)]
 pub struct Sha256Digest([u8; 32]);
 
+impl Sha256Digest {
+    /// Returns the raw digest bytes.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Returns the digest bytes in big-endian order.
+    ///
+    /// The native byte order, used for hashing and in blobs, is treated as little-endian, so these
+    /// are the native bytes reversed; the order some systems, eg. Bitcoin, display digests in.
+    #[inline]
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = self.0;
+        bytes.reverse();
+        bytes
+    }
+
+    /// Creates a digest from big-endian bytes, as returned by `to_be_bytes`.
+    #[inline]
+    pub fn from_be_bytes(mut bytes: [u8; 32]) -> Self {
+        bytes.reverse();
+        Self(bytes)
+    }
+}
+
+impl From<[u8; 32]> for Sha256Digest {
+    #[inline]
+    fn from(digest: [u8; 32]) -> Self {
+        Self(digest)
+    }
+}
+
+impl From<Sha256Digest> for [u8; 32] {
+    #[inline]
+    fn from(digest: Sha256Digest) -> Self {
+        digest.0
+    }
+}
+
+impl TryFrom<&'_ [u8]> for Sha256Digest {
+    type Error = TryFromSliceError;
+
+    #[inline]
+    fn try_from(buf: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; 32]>::try_from(buf).map(Self)
+    }
+}
+
 impl AsRef<[u8]> for Sha256Digest {
     #[inline]
     fn as_ref(&self) -> &[u8] {
@@ -92,4 +142,29 @@ mod test {
             hex!("7f83b1657ff1fc53b92dc18148a1d65dfc2d4b1fa3d677284addd200126d9069")
         );
     }
+
+    #[test]
+    fn be_bytes() {
+        let raw = hex!("7f83b1657ff1fc53b92dc18148a1d65dfc2d4b1fa3d677284addd200126d9069");
+        let digest = Sha256Digest::from(raw);
+
+        let be = digest.to_be_bytes();
+        assert_eq!(be, hex!("69906d1200d2dd4a2877d6a31f4b2dfc5dd6a14881c12db953fcf17f65b1837f"));
+        assert_eq!(digest.as_bytes(), &raw);
+
+        assert_eq!(Sha256Digest::from_be_bytes(be), digest);
+    }
+
+    #[test]
+    fn from_bytes() {
+        let raw = hex!("7f83b1657ff1fc53b92dc18148a1d65dfc2d4b1fa3d677284addd200126d9069");
+        let digest = Sha256Digest::from(raw);
+        assert_eq!(digest.as_bytes(), &raw);
+        assert_eq!(<[u8; 32]>::from(digest), raw);
+
+        assert_eq!(Sha256Digest::try_from(&raw[..]).unwrap(), digest);
+        assert!(Sha256Digest::try_from(&raw[1..]).is_err());
+        assert!(Sha256Digest::try_from(&[0u8; 33][..]).is_err());
+        assert!(Sha256Digest::try_from(&[][..]).is_err());
+    }
 }
@@ -0,0 +1,160 @@
+//! Truncated digest support.
+
+use std::convert::TryFrom;
+use std::array::TryFromSliceError;
+
+use hoard::blob::{Bytes, BytesUninit};
+use hoard::primitive::Primitive;
+
+use super::{Digest, Hasher, Sha256Hasher};
+
+/// A SHA256 digest truncated to its first `N` bytes.
+///
+/// `N` must be at most 32.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct TruncatedDigest<const N: usize>([u8; N]);
+
+impl<const N: usize> TruncatedDigest<N> {
+    /// Returns the raw digest bytes.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Default for TruncatedDigest<N> {
+    #[inline]
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for TruncatedDigest<N> {
+    #[inline]
+    fn from(digest: [u8; N]) -> Self {
+        Self(digest)
+    }
+}
+
+impl<const N: usize> TryFrom<&'_ [u8]> for TruncatedDigest<N> {
+    type Error = TryFromSliceError;
+
+    #[inline]
+    fn try_from(buf: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; N]>::try_from(buf).map(Self)
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for TruncatedDigest<N> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsMut<[u8]> for TruncatedDigest<N> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+/// A `Hasher` to create truncated SHA256 digests.
+///
+/// `N` must be at most 32; creating a hasher with a larger `N` fails to compile:
+///
+/// ```compile_fail
+/// # use proofmarshal_core::commit::TruncatedHasher;
+/// let _ = TruncatedHasher::<33>::default();
+/// ```
+pub struct TruncatedHasher<const N: usize>(Sha256Hasher);
+
+impl<const N: usize> TruncatedHasher<N> {
+    // Indexes out of bounds, failing const evaluation, if N is longer than a SHA256 digest.
+    const ASSERT_N_VALID: () = [()][(N > 32) as usize];
+}
+
+impl<const N: usize> Default for TruncatedHasher<N> {
+    #[inline]
+    fn default() -> Self {
+        let () = Self::ASSERT_N_VALID;
+        Self(Sha256Hasher::default())
+    }
+}
+
+impl<const N: usize> Digest for TruncatedDigest<N> {
+    type Hasher = TruncatedHasher<N>;
+}
+
+impl<const N: usize> Hasher for TruncatedHasher<N> {
+    type Output = TruncatedDigest<N>;
+
+    #[inline]
+    fn hash_bytes(&mut self, buf: &[u8]) {
+        self.0.hash_bytes(buf)
+    }
+
+    fn finish(self) -> Self::Output {
+        let () = Self::ASSERT_N_VALID;
+        let full = self.0.finish();
+        let mut digest = [0u8; N];
+        digest.copy_from_slice(&full.as_bytes()[.. N]);
+        TruncatedDigest(digest)
+    }
+}
+
+impl<const N: usize> Primitive for TruncatedDigest<N> {
+    const BLOB_SIZE: usize = N;
+    type DecodeBytesError = !;
+
+    #[inline]
+    fn encode_blob_bytes<'a>(&self, dst: BytesUninit<'a, Self>) -> Bytes<'a, Self> {
+        dst.write_bytes(&self.0)
+    }
+
+    #[inline]
+    fn decode_blob_bytes(src: Bytes<'_, Self>) -> Result<Self, Self::DecodeBytesError> {
+        let mut digest = [0u8; N];
+        digest.copy_from_slice(&src);
+        Ok(Self(digest))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex_literal::hex;
+
+    use hoard::blob::Blob;
+    use hoard::ptr::Heap;
+
+    use crate::collections::perfecttree::PerfectTree;
+    use crate::commit::Commit;
+
+    #[test]
+    fn truncates() {
+        let mut hasher = TruncatedHasher::<16>::default();
+        hasher.hash_bytes(b"Hello World!");
+        let digest = hasher.finish();
+        assert_eq!(digest.as_bytes(), &hex!("7f83b1657ff1fc53b92dc18148a1d65d"));
+    }
+
+    #[test]
+    fn truncated_tree() {
+        assert_eq!(<TruncatedDigest<16> as Blob>::SIZE, 16);
+
+        let leaves: Vec<_> = (0u8 .. 4).map(PerfectTree::<u8, Heap, TruncatedDigest<16>>::new_leaf).collect();
+        let mut leaves = leaves.into_iter();
+        let mut next = || leaves.next().unwrap();
+        let left = PerfectTree::try_join(next(), next()).unwrap();
+        let right = PerfectTree::try_join(next(), next()).unwrap();
+        let tree = PerfectTree::try_join(left, right).unwrap();
+
+        let root = tree.node_digest();
+        assert_eq!(root.as_ref().len(), 16);
+        assert_ne!(root, TruncatedDigest::default());
+        assert_eq!(tree.to_commitment().try_node_digest(), Some(root));
+    }
+}